@@ -0,0 +1,151 @@
+use std::io::{self, Read, Write};
+
+use glam::Vec3;
+
+use crate::{
+    checkpoint::{read_floats, read_words, write_floats, write_words},
+    colour::Colour,
+};
+
+/// Luminance below which noise is judged against this instead, so that near
+/// black pixels, whose relative noise can't be seen, aren't sampled forever
+const MIN_LUMINANCE: f32 = 0.01;
+
+/// Two-sided 95% quantile of the normal distribution
+const CONFIDENCE_SCALE: f32 = 1.96;
+
+/// Stops sampling each pixel once its estimate is precise enough, then
+/// hands the samples those pixels didn't need to the noisiest of the rest
+#[derive(Copy, Clone, Debug)]
+pub struct AdaptiveSampling {
+    /// Samples every pixel takes before its noise is trusted
+    pub min_samples: u16,
+    /// The most samples any one pixel takes
+    pub max_samples: u16,
+    /// Largest half-width of the 95% confidence interval of a pixel's
+    /// luminance, relative to the luminance itself
+    pub noise_threshold: f32,
+}
+
+impl AdaptiveSampling {
+    pub fn is_converged(&self, statistics: &PixelStatistics) -> bool {
+        statistics.count >= u32::from(self.min_samples.max(2))
+            && self.noise_ratio(statistics) <= 1.
+    }
+
+    /// The half-width of the pixel's confidence interval over the largest
+    /// it's allowed to be
+    fn noise_ratio(&self, statistics: &PixelStatistics) -> f32 {
+        let luminance = statistics.mean_luminance.max(MIN_LUMINANCE);
+        let half_width = CONFIDENCE_SCALE
+            * (statistics.variance() / statistics.count.max(1) as f32).sqrt();
+        half_width / (self.noise_threshold * luminance)
+    }
+
+    /// How many samples each pixel should end up with once the budget of
+    /// `samples` per pixel that converged pixels left unused is handed out.
+    /// The noisiest pixels come first, each getting as many as it looks to
+    /// need to converge, up to `max_samples`.
+    pub fn redistribute(
+        &self,
+        statistics: &[PixelStatistics],
+        samples: u16,
+    ) -> Vec<u32> {
+        let mut targets = statistics
+            .iter()
+            .map(PixelStatistics::count)
+            .collect::<Vec<_>>();
+        let used = targets.iter().copied().map(u64::from).sum::<u64>();
+        let budget = statistics.len() as u64 * u64::from(samples);
+        let mut spare = budget.saturating_sub(used);
+
+        let mut noisy = statistics
+            .iter()
+            .enumerate()
+            .filter(|(_, statistics)| !self.is_converged(statistics))
+            .map(|(index, statistics)| (index, self.noise_ratio(statistics)))
+            .collect::<Vec<_>>();
+        // Stable, so that equally noisy pixels keep their order
+        noisy.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+        for (index, ratio) in noisy {
+            if spare == 0 {
+                break;
+            }
+            // The interval narrows with the square root of the count
+            let count = targets[index];
+            let needed = (count as f32 * (ratio * ratio - 1.)).ceil() as u64;
+            let room = u32::from(self.max_samples).saturating_sub(count);
+            let extra = needed.min(u64::from(room)).min(spare);
+            targets[index] += extra as u32;
+            spare -= extra;
+        }
+        targets
+    }
+}
+
+/// Running mean of a pixel's samples, and the variance of their luminance
+/// by Welford's algorithm
+#[derive(Clone, Debug, Default)]
+pub struct PixelStatistics {
+    count: u32,
+    sum: Colour,
+    mean_luminance: f32,
+    squared_deviations: f32,
+}
+
+impl PixelStatistics {
+    pub fn add(&mut self, colour: Colour) {
+        self.count += 1;
+        self.sum += colour;
+        let luminance = colour.luminance();
+        let deviation = luminance - self.mean_luminance;
+        self.mean_luminance += deviation / self.count as f32;
+        self.squared_deviations +=
+            deviation * (luminance - self.mean_luminance);
+    }
+
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    pub fn mean(&self) -> Colour {
+        if self.count == 0 {
+            return Colour::BLACK;
+        }
+        self.sum * (1. / self.count as f32)
+    }
+
+    /// Sample variance of the luminance
+    pub fn variance(&self) -> f32 {
+        if self.count < 2 {
+            return 0.;
+        }
+        self.squared_deviations / (self.count - 1) as f32
+    }
+
+    pub(crate) fn write(&self, out: &mut impl Write) -> io::Result<()> {
+        write_words(out, &[self.count])?;
+        let sum = Vec3::from(self.sum);
+        write_floats(
+            out,
+            &[
+                sum.x,
+                sum.y,
+                sum.z,
+                self.mean_luminance,
+                self.squared_deviations,
+            ],
+        )
+    }
+
+    pub(crate) fn read(input: &mut impl Read) -> io::Result<Self> {
+        let [count] = read_words(input)?;
+        let [r, g, b, mean_luminance, squared_deviations] = read_floats(input)?;
+        Ok(Self {
+            count,
+            sum: Colour::new(r, g, b),
+            mean_luminance,
+            squared_deviations,
+        })
+    }
+}
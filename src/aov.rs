@@ -0,0 +1,89 @@
+use clap::ValueEnum;
+use image::{Rgb, Rgb32FImage};
+use serde::Deserialize;
+
+use crate::{colour::Colour, film::Frame, random::mix};
+
+/// A buffer saved alongside the image, describing what each pixel saw first
+#[derive(Clone, Copy, Debug, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Aov {
+    /// Distance from the camera, or zero for the sky
+    Depth,
+    /// World space normal
+    Normal,
+    /// Surface colour, without lighting
+    Albedo,
+    /// Texture coordinates, in the red and green channels
+    Uv,
+    /// One more than the order the scene defined the object in
+    Object,
+    /// The order the scene defined the material in
+    Material,
+    /// Surfaces hit by the whole path
+    Hits,
+}
+
+impl Aov {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Depth => "depth",
+            Self::Normal => "normal",
+            Self::Albedo => "albedo",
+            Self::Uv => "uv",
+            Self::Object => "object",
+            Self::Material => "material",
+            Self::Hits => "hits",
+        }
+    }
+
+    /// The raw values, for saving in float formats
+    pub fn image(self, frame: &Frame) -> &Rgb32FImage {
+        match self {
+            Self::Depth => &frame.depth,
+            Self::Normal => &frame.normal,
+            Self::Albedo => &frame.albedo,
+            Self::Uv => &frame.uv,
+            Self::Object => &frame.object,
+            Self::Material => &frame.material,
+            Self::Hits => &frame.hits,
+        }
+    }
+
+    /// The values brought into [0, 1] to be looked at, with numbers given
+    /// distinct colours
+    pub fn visualise(self, frame: &Frame) -> Rgb32FImage {
+        let image = self.image(frame);
+        let mut visual = image.clone();
+        match self {
+            Self::Albedo | Self::Uv => {}
+            Self::Normal => visual
+                .pixels_mut()
+                .for_each(|pixel| pixel.0 = pixel.0.map(|x| 0.5 * (x + 1.))),
+            Self::Depth | Self::Hits => {
+                let max =
+                    image.pixels().map(|pixel| pixel.0[0]).fold(0., f32::max);
+                if max > 0. {
+                    visual
+                        .pixels_mut()
+                        .for_each(|pixel| pixel.0 = pixel.0.map(|x| x / max));
+                }
+            }
+            Self::Object | Self::Material => visual
+                .pixels_mut()
+                .for_each(|pixel| *pixel = id_colour(pixel.0[0] as u32)),
+        }
+        visual
+    }
+}
+
+/// A bright colour picked by hashing `id`, or black for zero
+fn id_colour(id: u32) -> Rgb<f32> {
+    if id == 0 {
+        return Colour::BLACK.into();
+    }
+    let hash = mix(u64::from(id));
+    let channel =
+        |shift: u32| 0.2 + 0.8 * ((hash >> shift) & 0xff) as f32 / 255.;
+    Colour::new(channel(0), channel(8), channel(16)).into()
+}
@@ -1,16 +1,26 @@
-use std::sync::{Arc, Mutex};
+use std::time::Instant;
 
 use crate::{
-    colour::{Colour, map_colours},
+    adaptive::{AdaptiveSampling, PixelStatistics},
+    checkpoint::Checkpoint,
+    colour::Colour,
+    film::{Features, Film},
+    filter::Filter,
     geometry::{Point3, Ray, VecRand, make_basis},
-    hittables::hittable::{Hittable, HittableList},
+    hittables::{
+        hittable::{HitRecord, Hittable, HittableList},
+        light::{LightList, power_heuristic},
+    },
     interval::Interval,
+    profile::Profile,
+    progress::Progress,
+    sampler::{Sample, Sampler, SamplerKind},
+    scene::{CameraSettings, Sky},
     textures::texture::GetTexture,
+    tile::{Tile, TileOrder, tiles},
 };
-use glam::Vec3;
-use image::{Rgb, RgbImage};
-use rand::{Rng, rng};
-use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use glam::{Vec2, Vec3};
+use rayon::iter::{ParallelBridge, ParallelIterator};
 
 #[derive(Debug)]
 pub struct Camera {
@@ -21,35 +31,46 @@ pub struct Camera {
     horizontal_pixel_delta: Vec3,
     vertical_pixel_delta: Vec3,
     rays_per_pixel: u16,
-    pixel_sample_scale: f32,
+    max_pixel_samples: u16,
+    pass_samples: u16,
+    adaptive: Option<AdaptiveSampling>,
+    filter: Filter,
+    tile_size: u32,
+    tile_order: TileOrder,
     max_ray_bounces: u16,
     defocus_angle: f32,
     defocus_disk_horiz_radius: Vec3,
     defocus_disk_vert_radius: Vec3,
     sky_top_colour: Colour,
     sky_bottom_colour: Colour,
+    seed: u64,
+    sampler: SamplerKind,
 }
 
-pub type ProfileInfo = (u32, u16, u16);
-pub type CameraInfo = (Point3, Point3, f32, f32, f32, Colour, Colour, f32, f32);
-
 impl Camera {
     pub fn initialise(
-        (image_width, rays_per_pixel, max_ray_bounces): ProfileInfo,
-        (
+        profile @ &Profile {
+            width: image_width,
+            samples: rays_per_pixel,
+            max_bounces: max_ray_bounces,
+            seed,
+            sampler,
+            tile_size,
+            tile_order,
+            ..
+        }: &Profile,
+        camera_settings @ &CameraSettings {
             look_from,
             look_at,
-            camera_tilt,
+            tilt: camera_tilt,
             fov,
-            aspect_ratio,
-            sky_top_colour,
-            sky_bottom_colour,
             focus_distance,
             defocus_angle,
-        ): CameraInfo,
+            ..
+        }: &CameraSettings,
+        sky: &Sky,
     ) -> Camera {
-        let image_height = (image_width as f32 / aspect_ratio).floor() as u32;
-        let pixel_sample_scale = 1. / f32::from(rays_per_pixel);
+        let image_height = camera_settings.image_height(image_width);
 
         let camera_center = look_from;
 
@@ -88,43 +109,42 @@ impl Camera {
             horizontal_pixel_delta,
             vertical_pixel_delta,
             rays_per_pixel,
-            pixel_sample_scale,
+            max_pixel_samples: profile.max_pixel_samples(),
+            pass_samples: profile.pass_samples.unwrap_or(rays_per_pixel).max(1),
+            adaptive: profile.adaptive(),
+            filter: profile.filter(),
+            tile_size,
+            tile_order,
             max_ray_bounces,
             defocus_angle,
             defocus_disk_horiz_radius,
             defocus_disk_vert_radius,
-            sky_top_colour,
-            sky_bottom_colour,
+            sky_top_colour: sky.top,
+            sky_bottom_colour: sky.bottom,
+            seed,
+            sampler,
         }
     }
 
-    fn ray_colour(&self, mut ray: Ray, world: &HittableList) -> Colour {
+    /// The radiance arriving along `ray`, and the features of what it
+    /// first hit
+    fn ray_colour(
+        &self,
+        mut ray: Ray,
+        world: &HittableList,
+        lights: &LightList,
+        sampler: &mut Sampler,
+    ) -> (Colour, Features) {
         let mut accumulated = Colour::WHITE;
-        let mut light_hit = false;
-        for _ in 0..self.max_ray_bounces {
-            if let Some(data) =
+        let mut radiance = Colour::BLACK;
+        let mut features = Features::default();
+        // Density of the last bounce, if its surface also sampled the lights
+        let mut bounce_pdf = None;
+        for bounce in 0..self.max_ray_bounces {
+            let Some(data) =
                 world.was_hit(ray, Interval::new(0.001, f32::INFINITY))
-            {
-                let (u, v) = (data.u, data.v);
-
-                let material = data.clone().material;
-
-                if material.is_light {
-                    accumulated *= material.texture.get_colour(u, v);
-                    light_hit = true;
-                    break;
-                }
-
-                if material.is_glass {
-                    material.refract(&mut ray, &data)
-                } else {
-                    material.lerp_reflect(&mut ray, &data)
-                };
-
-                accumulated *= material.texture.get_colour(u, v);
-            } else {
-                light_hit = true;
-                accumulated *= {
+            else {
+                let sky = {
                     let unit_vector = ray.direction.normalize();
                     let vert_ratio = 0.5 * (unit_vector.y + 1.);
 
@@ -134,93 +154,277 @@ impl Camera {
                         vert_ratio,
                     )
                 };
+                if bounce == 0 {
+                    features.albedo = sky;
+                }
+                radiance += accumulated * sky;
+                break;
+            };
+            let (u, v) = (data.u, data.v);
+
+            let material = data.clone().material;
+            if bounce == 0 {
+                features = Features {
+                    albedo: material.texture.get_colour(u, v),
+                    normal: data.normal_vector,
+                    depth: (data.collision_point - ray.origin).length(),
+                    uv: Vec2::new(u, v),
+                    object: data.object as u32 + 1,
+                    material: material.id,
+                    hits: 0,
+                };
+            }
+            features.hits += 1;
+
+            if material.is_light {
+                let weight = bounce_pdf.map_or(1., |pdf| {
+                    let light_pdf =
+                        lights.pdf_from(ray.origin, ray.direction.normalize());
+                    power_heuristic(pdf, light_pdf)
+                });
+                radiance +=
+                    accumulated * material.texture.get_colour(u, v) * weight;
                 break;
             }
+
+            let samples_lights =
+                material.samples_lights() && !lights.is_empty();
+            if samples_lights {
+                radiance += accumulated
+                    * Self::direct_light(ray, &data, world, lights, sampler);
+            }
+
+            let Some(scatter) = material.scatter(&mut ray, &data, sampler)
+            else {
+                break;
+            };
+            bounce_pdf = scatter.pdf.filter(|_| samples_lights);
+            accumulated *= scatter.attenuation;
         }
-        if light_hit {
-            accumulated
-        } else {
-            Colour::BLACK
+        (radiance, features)
+    }
+
+    /// Light reflected along `ray` from a sampled point on a light, weighted
+    /// against the chance of the bounce reaching it instead
+    fn direct_light(
+        ray: Ray,
+        record: &HitRecord,
+        world: &HittableList,
+        lights: &LightList,
+        sampler: &mut Sampler,
+    ) -> Colour {
+        let Some(sample) = lights.sample_from(record.collision_point, sampler)
+        else {
+            return Colour::BLACK;
+        };
+        let Some((reflectance, bsdf_pdf)) =
+            record
+                .material
+                .evaluate(record, ray.direction, sample.direction)
+        else {
+            return Colour::BLACK;
+        };
+        if bsdf_pdf <= 0. || sample.pdf <= 0. {
+            return Colour::BLACK;
         }
+        let shadow_ray = Ray::new(record.collision_point, sample.direction);
+        if world
+            .was_hit(shadow_ray, Interval::new(0.001, sample.distance - 0.001))
+            .is_some()
+        {
+            return Colour::BLACK;
+        }
+        let weight = power_heuristic(sample.pdf, bsdf_pdf);
+        sample.emitted * reflectance * (weight / sample.pdf)
     }
 
-    pub fn render(&self, world: &HittableList, report_count: u32) -> RgbImage {
-        if report_count != 0 {
-            let pixel_count = self.image_height * self.image_width;
-            assert!(pixel_count.is_multiple_of(report_count));
-            println!("0% done (0/{pixel_count})");
-            let pixel_report_increment = pixel_count / report_count;
-            let done_pixels = Arc::new(Mutex::new(0u32));
-            RgbImage::from_par_fn(
-                self.image_width,
-                self.image_height,
-                |i, j| {
-                    let colour = self.get_pixel_colour(i, j, world);
-                    let mut done = *done_pixels.lock().unwrap();
-                    done += 1;
-                    if done.is_multiple_of(pixel_report_increment) {
-                        println!(
-                            "{}% done ({done}/{pixel_count})",
-                            100. * (done as f64) / (pixel_count as f64),
-                        );
-                    }
-                    colour
-                },
-            )
-        } else {
-            RgbImage::from_par_fn(
-                self.image_width,
-                self.image_height,
-                |i, j| self.get_pixel_colour(i, j, world),
-            )
+    /// Adds samples to `checkpoint` in passes, until every pixel has the
+    /// profile's samples or adaptive sampling has stopped it. Adaptive
+    /// sampling then spends what the stopped pixels saved on the noisiest
+    /// ones in a last pass. Every random choice is derived from the seed, so
+    /// the image only depends on the settings and where the passes end.
+    pub fn render(
+        &self,
+        world: &HittableList,
+        checkpoint: &mut Checkpoint,
+        show_progress: bool,
+        mut between_passes: impl FnMut(&Checkpoint),
+    ) {
+        let lights = LightList::from_objects(world.objects());
+        // Passes end at multiples of their size, so that carrying on from a
+        // checkpoint lines up with the render that saved it
+        let mut pass_ends = Vec::new();
+        let mut samples = checkpoint.samples;
+        while samples < self.rays_per_pixel {
+            samples = (samples / self.pass_samples + 1)
+                .saturating_mul(self.pass_samples)
+                .min(self.rays_per_pixel);
+            pass_ends.push(samples);
+        }
+        if pass_ends.is_empty() {
+            return;
         }
+
+        let tiles = tiles(
+            self.image_width,
+            self.image_height,
+            self.tile_size,
+            self.tile_order,
+        );
+        let passes = pass_ends.len() + usize::from(self.adaptive.is_some());
+        let progress =
+            Progress::new((tiles.len() * passes) as u64, show_progress);
+        for (pass, &samples) in pass_ends.iter().enumerate() {
+            if pass > 0 {
+                between_passes(checkpoint);
+            }
+            self.render_pass(
+                world,
+                &lights,
+                &tiles,
+                &progress,
+                checkpoint,
+                |_| u32::from(samples),
+            );
+            checkpoint.samples = samples;
+        }
+        // Not saved first, as a checkpoint only records whole passes of
+        // the profile's samples. Carrying on from one taken before the last
+        // of those redoes this too.
+        if let Some(adaptive) = self.adaptive {
+            let targets = adaptive
+                .redistribute(&checkpoint.statistics, self.rays_per_pixel);
+            self.render_pass(
+                world,
+                &lights,
+                &tiles,
+                &progress,
+                checkpoint,
+                |index| targets[index],
+            );
+        }
+        progress.finish();
     }
 
-    fn get_pixel_colour(
+    /// Renders every tile until each pixel has the number of samples
+    /// `target` gives for its index, and adds them to `checkpoint`
+    fn render_pass(
         &self,
-        i: u32,
-        j: u32,
         world: &HittableList,
-    ) -> Rgb<u8> {
-        let colour = (0..self.rays_per_pixel)
-            .into_par_iter()
-            .map(|_| {
-                let ray = self.get_ray(i, j);
-                self.ray_colour(ray, world)
+        lights: &LightList,
+        tiles: &[Tile],
+        progress: &Progress,
+        checkpoint: &mut Checkpoint,
+        target: impl Fn(usize) -> u32 + Sync,
+    ) {
+        // Tiles are handed out in order as threads become free, each
+        // rendering into a film reaching as far as the filter does
+        let padding = self.filter.radius.ceil() as u32;
+        let statistics = &checkpoint.statistics;
+        let mut rendered = tiles
+            .iter()
+            .par_bridge()
+            .map(|&tile| {
+                let start = Instant::now();
+                let mut film = Film::new(
+                    i64::from(tile.left) - i64::from(padding),
+                    i64::from(tile.top) - i64::from(padding),
+                    tile.width + 2 * padding,
+                    tile.height + 2 * padding,
+                );
+                let tile_statistics = tile
+                    .pixels()
+                    .map(|(i, j)| {
+                        let index = (j * self.image_width + i) as usize;
+                        let mut pixel_statistics = statistics[index].clone();
+                        self.render_pixel(
+                            (i, j),
+                            target(index),
+                            world,
+                            lights,
+                            &mut film,
+                            &mut pixel_statistics,
+                        );
+                        pixel_statistics
+                    })
+                    .collect::<Vec<_>>();
+                progress.tile_done(start.elapsed());
+                (tile, film, tile_statistics)
             })
-            .sum::<Colour>()
-            * self.pixel_sample_scale;
-        let (r, g, b) = map_colours(&colour);
-        Rgb([r, g, b])
+            .collect::<Vec<_>>();
+
+        // Merged in order of position, so that the image doesn't depend on
+        // which tile finishes first
+        rendered.sort_unstable_by_key(|(tile, ..)| (tile.top, tile.left));
+        for (tile, film, tile_statistics) in rendered {
+            checkpoint.film.merge(&film);
+            for ((i, j), pixel_statistics) in tile.pixels().zip(tile_statistics)
+            {
+                checkpoint.statistics[(j * self.image_width + i) as usize] =
+                    pixel_statistics;
+            }
+        }
     }
 
-    fn get_ray(&self, horiz_position: u32, vert_position: u32) -> Ray {
-        let offset = Self::sample_square(horiz_position, vert_position);
+    /// Splats samples into `film` until the pixel has `samples` of them
+    fn render_pixel(
+        &self,
+        (i, j): (u32, u32),
+        samples: u32,
+        world: &HittableList,
+        lights: &LightList,
+        film: &mut Film,
+        statistics: &mut PixelStatistics,
+    ) {
+        // Samples are splatted in order, as floating point addition isn't
+        // associative
+        for sample in statistics.count()..samples {
+            if self
+                .adaptive
+                .is_some_and(|adaptive| adaptive.is_converged(statistics))
+            {
+                break;
+            }
+            let mut sampler = self.sampler.start(
+                self.seed,
+                (i, j),
+                sample,
+                u32::from(self.max_pixel_samples),
+            );
+            let offset = sampler.get_2d() - 0.5;
+            let ray = self.get_ray(i, j, offset, &mut sampler);
+            let (colour, features) =
+                self.ray_colour(ray, world, lights, &mut sampler);
+            film.splat((i, j), offset, colour, &self.filter);
+            film.record((i, j), features);
+            statistics.add(colour);
+        }
+    }
+
+    /// A ray through the point `offset` from the centre of the pixel
+    fn get_ray(
+        &self,
+        horiz_position: u32,
+        vert_position: u32,
+        offset: Vec2,
+        sampler: &mut Sampler,
+    ) -> Ray {
+        let position =
+            Vec2::new(horiz_position as f32, vert_position as f32) + offset;
         let pixel_sample = *self.pixel_upper_left
-            + offset.x * self.horizontal_pixel_delta
-            + offset.y * self.vertical_pixel_delta;
+            + position.x * self.horizontal_pixel_delta
+            + position.y * self.vertical_pixel_delta;
         let ray_origin = if self.defocus_angle <= 0. {
             self.center
         } else {
-            self.defocus_disk_sample()
+            self.defocus_disk_sample(sampler)
         };
         let ray_direction = pixel_sample - *ray_origin;
         Ray::new(ray_origin, ray_direction)
     }
 
-    fn sample_square(horiz_position: u32, vert_position: u32) -> Vec3 {
-        let mut rng = rng();
-        let horiz_offset = rng.random_range(-0.5..0.5);
-        let vert_offset = rng.random_range(-0.5..0.5);
-        Vec3::new(
-            (horiz_position as f32) + horiz_offset,
-            (vert_position as f32) + vert_offset,
-            0.,
-        )
-    }
-
-    fn defocus_disk_sample(&self) -> Point3 {
-        let point = Vec3::random_on_unit_disk();
+    fn defocus_disk_sample(&self, sampler: &mut Sampler) -> Point3 {
+        let point = Vec3::random_on_unit_disk(sampler);
         self.center
             + (point.x * self.defocus_disk_horiz_radius)
             + (point.y * self.defocus_disk_vert_radius)
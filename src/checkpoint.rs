@@ -0,0 +1,560 @@
+use std::{
+    fmt,
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+    time::UNIX_EPOCH,
+};
+
+use clap::ValueEnum;
+use thiserror::Error;
+
+use crate::{
+    adaptive::PixelStatistics, film::Film, filter::Filter, profile::Profile,
+    sampler::SamplerKind, scene::Scene,
+};
+
+/// Starts every checkpoint file, with the version of its layout at the end
+const MAGIC: &[u8; 8] = b"RTCKPT03";
+
+#[derive(Debug, Error)]
+pub enum CheckpointError {
+    #[error("unable to read checkpoint {path:?}: {source}")]
+    Read { path: String, source: io::Error },
+    #[error("unable to write checkpoint {path:?}: {source}")]
+    Write { path: String, source: io::Error },
+    #[error("{path:?} is not a valid checkpoint")]
+    Invalid { path: String },
+    #[error(
+        "the checkpoint was rendered with {setting} {checkpoint}, not {expected}"
+    )]
+    Mismatch {
+        setting: &'static str,
+        checkpoint: String,
+        expected: String,
+    },
+    #[error("the scene has changed since the checkpoint was saved")]
+    SceneChanged,
+    #[error("the checkpoint's buffers don't cover its {width}x{height} image")]
+    WrongSize { width: u32, height: u32 },
+}
+
+/// The settings a checkpoint's samples depend on, which a render carrying
+/// it on has to share
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CheckpointSettings {
+    /// A hash of the scene's source and the files it read
+    pub scene: u64,
+    pub width: u32,
+    pub height: u32,
+    pub seed: u64,
+    pub sampler: SamplerKind,
+    /// The most samples any pixel takes, which each pixel's samples are
+    /// spread over. It only has to match for samplers that aren't
+    /// progressive.
+    pub samples: u16,
+    pub filter: Filter,
+    pub max_bounces: u16,
+}
+
+impl CheckpointSettings {
+    pub fn new(scene: &Scene, profile: &Profile) -> Self {
+        Self {
+            scene: scene_hash(scene),
+            width: profile.width,
+            height: scene.camera.image_height(profile.width),
+            seed: profile.seed,
+            sampler: profile.sampler,
+            samples: profile.max_pixel_samples(),
+            filter: profile.filter(),
+            max_bounces: profile.max_bounces,
+        }
+    }
+
+    /// Checks that a render with `expected` can carry on from these
+    pub fn check(&self, expected: &Self) -> Result<(), CheckpointError> {
+        if self.scene != expected.scene {
+            return Err(CheckpointError::SceneChanged);
+        }
+        let filter = |filter: &Filter| {
+            format!("{} of radius {}", name(filter.kind), filter.radius)
+        };
+        let samples = |settings: &Self| {
+            if settings.sampler.is_progressive() {
+                String::from("any")
+            } else {
+                settings.samples.to_string()
+            }
+        };
+        let settings = [
+            ("width", self.width.to_string(), expected.width.to_string()),
+            (
+                "height",
+                self.height.to_string(),
+                expected.height.to_string(),
+            ),
+            ("seed", self.seed.to_string(), expected.seed.to_string()),
+            ("sampler", name(self.sampler), name(expected.sampler)),
+            ("total samples", samples(self), samples(expected)),
+            ("filter", filter(&self.filter), filter(&expected.filter)),
+            (
+                "max bounces",
+                self.max_bounces.to_string(),
+                expected.max_bounces.to_string(),
+            ),
+        ];
+        match settings
+            .into_iter()
+            .find(|(_, ours, theirs)| ours != theirs)
+        {
+            Some((setting, checkpoint, expected)) => {
+                Err(CheckpointError::Mismatch {
+                    setting,
+                    checkpoint,
+                    expected,
+                })
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+/// A render's progress, from which it can be carried on to more samples.
+/// As every sample's random numbers come from the seed, the pixel and the
+/// sample's number, the seed and the sample counts stand in for the state
+/// of the generators.
+#[derive(Clone, Debug)]
+pub struct Checkpoint {
+    pub settings: CheckpointSettings,
+    /// Samples every pixel has been rendered up to, though adaptive
+    /// sampling may have stopped some sooner
+    pub samples: u16,
+    /// Filter weighted sums of the samples over the whole image
+    pub film: Film,
+    /// The samples taken in each pixel, row by row
+    pub statistics: Vec<PixelStatistics>,
+}
+
+impl Checkpoint {
+    /// A render with no samples yet
+    pub fn new(settings: CheckpointSettings) -> Self {
+        Self {
+            settings,
+            samples: 0,
+            film: Film::new(0, 0, settings.width, settings.height),
+            statistics: vec![
+                PixelStatistics::default();
+                (settings.width * settings.height) as usize
+            ],
+        }
+    }
+
+    /// Checks that a render with `expected` can carry on from the
+    /// checkpoint, and that its buffers are the size its settings say
+    pub fn check(
+        &self,
+        expected: &CheckpointSettings,
+    ) -> Result<(), CheckpointError> {
+        self.settings.check(expected)?;
+        let CheckpointSettings { width, height, .. } = self.settings;
+        let pixel_count = u64::from(width) * u64::from(height);
+        if self.film.size() != (width, height)
+            || self.statistics.len() as u64 != pixel_count
+        {
+            return Err(CheckpointError::WrongSize { width, height });
+        }
+        Ok(())
+    }
+
+    /// Writes the checkpoint to `path` by way of a file beside it, so that
+    /// being stopped part way through leaves any earlier checkpoint whole
+    pub fn save(&self, path: &Path) -> Result<(), CheckpointError> {
+        let partial = path.with_added_extension("partial");
+        File::create(&partial)
+            .and_then(|file| self.write(&mut BufWriter::new(file)))
+            .and_then(|()| fs::rename(&partial, path))
+            .map_err(|source| CheckpointError::Write {
+                path: path.display().to_string(),
+                source,
+            })
+    }
+
+    pub fn load(path: &Path) -> Result<Self, CheckpointError> {
+        let path_string = path.display().to_string();
+        File::open(path)
+            .and_then(|file| Self::read(&mut BufReader::new(file)))
+            .map_err(|source| match source.kind() {
+                io::ErrorKind::InvalidData | io::ErrorKind::UnexpectedEof => {
+                    CheckpointError::Invalid { path: path_string }
+                }
+                _ => CheckpointError::Read {
+                    path: path_string,
+                    source,
+                },
+            })
+    }
+
+    fn write(&self, out: &mut impl Write) -> io::Result<()> {
+        let settings = &self.settings;
+        out.write_all(MAGIC)?;
+        write_words(
+            out,
+            &[
+                settings.scene as u32,
+                (settings.scene >> 32) as u32,
+                settings.width,
+                settings.height,
+                settings.seed as u32,
+                (settings.seed >> 32) as u32,
+                settings.samples.into(),
+                settings.max_bounces.into(),
+                self.samples.into(),
+            ],
+        )?;
+        write_name(out, settings.sampler)?;
+        write_name(out, settings.filter.kind)?;
+        write_floats(out, &[settings.filter.radius])?;
+        self.film.write(out)?;
+        for statistics in &self.statistics {
+            statistics.write(out)?;
+        }
+        out.flush()
+    }
+
+    fn read(input: &mut impl Read) -> io::Result<Self> {
+        let mut magic = [0; MAGIC.len()];
+        input.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(io::ErrorKind::InvalidData.into());
+        }
+        let [
+            scene_low,
+            scene_high,
+            width,
+            height,
+            seed_low,
+            seed_high,
+            total_samples,
+            max_bounces,
+            samples,
+        ] = read_words(input)?;
+        let sampler = read_name(input)?;
+        let kind = read_name(input)?;
+        let [radius] = read_floats(input)?;
+        let (
+            Ok(total_samples),
+            Ok(max_bounces),
+            Ok(samples),
+            Some(pixel_count),
+        ) = (
+            total_samples.try_into(),
+            max_bounces.try_into(),
+            samples.try_into(),
+            width.checked_mul(height),
+        )
+        else {
+            return Err(io::ErrorKind::InvalidData.into());
+        };
+        let settings = CheckpointSettings {
+            scene: u64::from(scene_low) | (u64::from(scene_high) << 32),
+            width,
+            height,
+            seed: u64::from(seed_low) | (u64::from(seed_high) << 32),
+            sampler,
+            samples: total_samples,
+            filter: Filter { kind, radius },
+            max_bounces,
+        };
+        let film = Film::read(input, width, height)?;
+        let statistics = (0..pixel_count)
+            .map(|_| PixelStatistics::read(input))
+            .collect::<io::Result<_>>()?;
+        Ok(Self {
+            settings,
+            samples,
+            film,
+            statistics,
+        })
+    }
+}
+
+/// The scene's source hash if it was read from a file. Scenes built in
+/// code have no source, so their camera, sky and number of objects are
+/// hashed instead, which is cheap but misses changes to the objects
+/// themselves.
+fn scene_hash(scene: &Scene) -> u64 {
+    scene.source_hash.unwrap_or_else(|| {
+        let mut hasher = SceneHasher::default();
+        // Writing to the hasher can't fail
+        let _ = fmt::write(
+            &mut hasher,
+            format_args!(
+                "{:?}{:?}{}",
+                scene.camera,
+                scene.sky,
+                scene.world.objects().len()
+            ),
+        );
+        hasher.finish()
+    })
+}
+
+/// Hashes what a scene was built from, to tell whether a checkpoint was
+/// rendered from it. FNV-1a is used rather than the standard library's
+/// hasher, whose output may change between releases.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct SceneHasher(u64);
+
+impl Default for SceneHasher {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl SceneHasher {
+    pub(crate) fn add(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 =
+                (self.0 ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    /// Adds the path of a file and when it was last changed, which is much
+    /// quicker than adding what it holds
+    pub(crate) fn add_file(&mut self, path: &Path) {
+        self.add(path.as_os_str().as_encoded_bytes());
+        let modified = fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .unwrap_or_default();
+        self.add(&modified.as_nanos().to_le_bytes());
+    }
+
+    pub(crate) fn finish(self) -> u64 {
+        self.0
+    }
+}
+
+impl fmt::Write for SceneHasher {
+    fn write_str(&mut self, text: &str) -> fmt::Result {
+        self.add(text.as_bytes());
+        Ok(())
+    }
+}
+
+/// The name `value` is given on the command line and in profiles
+fn name(value: impl ValueEnum) -> String {
+    value
+        .to_possible_value()
+        .map_or_else(String::new, |value| value.get_name().to_owned())
+}
+
+fn write_name(out: &mut impl Write, value: impl ValueEnum) -> io::Result<()> {
+    let name = name(value);
+    write_words(out, &[name.len() as u32])?;
+    out.write_all(name.as_bytes())
+}
+
+fn read_name<T: ValueEnum>(input: &mut impl Read) -> io::Result<T> {
+    let [length] = read_words(input)?;
+    let mut name = vec![0; length.min(64) as usize];
+    input.read_exact(&mut name)?;
+    String::from_utf8(name)
+        .ok()
+        .and_then(|name| T::from_str(&name, false).ok())
+        .ok_or(io::ErrorKind::InvalidData.into())
+}
+
+/// Writes `words` in little-endian order
+pub(crate) fn write_words(
+    out: &mut impl Write,
+    words: &[u32],
+) -> io::Result<()> {
+    words
+        .iter()
+        .try_for_each(|word| out.write_all(&word.to_le_bytes()))
+}
+
+pub(crate) fn read_words<const N: usize>(
+    input: &mut impl Read,
+) -> io::Result<[u32; N]> {
+    let mut words = [0; N];
+    let mut bytes = [0; 4];
+    for word in &mut words {
+        input.read_exact(&mut bytes)?;
+        *word = u32::from_le_bytes(bytes);
+    }
+    Ok(words)
+}
+
+pub(crate) fn write_floats(
+    out: &mut impl Write,
+    floats: &[f32],
+) -> io::Result<()> {
+    floats
+        .iter()
+        .try_for_each(|float| out.write_all(&float.to_le_bytes()))
+}
+
+pub(crate) fn read_floats<const N: usize>(
+    input: &mut impl Read,
+) -> io::Result<[f32; N]> {
+    Ok(read_words::<N>(input)?.map(f32::from_bits))
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec2;
+
+    use super::*;
+    use crate::{colour::Colour, filter::FilterKind};
+
+    fn checkpoint() -> Checkpoint {
+        let mut checkpoint = Checkpoint::new(CheckpointSettings {
+            scene: 0x0123_4567_89ab_cdef,
+            width: 3,
+            height: 2,
+            seed: u64::MAX - 7,
+            sampler: SamplerKind::Sobol,
+            samples: 64,
+            filter: Filter::new(FilterKind::Gaussian, Some(1.5)),
+            max_bounces: 12,
+        });
+        checkpoint.samples = 16;
+        let filter = checkpoint.settings.filter;
+        checkpoint.film.splat(
+            (1, 0),
+            Vec2::new(0.1, 0.3),
+            Colour::new(0.5, 1., 2.),
+            &filter,
+        );
+        checkpoint.statistics[4].add(Colour::new(0.2, 0.4, 0.8));
+        checkpoint.statistics[4].add(Colour::new(0.6, 0.2, 0.1));
+        checkpoint
+    }
+
+    fn bytes(checkpoint: &Checkpoint) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        checkpoint.write(&mut bytes).unwrap();
+        bytes
+    }
+
+    /// A path in the temporary directory that no other test uses
+    fn temporary_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!(
+            "ray_tracing_{}_{name}.checkpoint",
+            std::process::id()
+        ))
+    }
+
+    #[test]
+    fn save_then_load_gives_the_same_checkpoint() {
+        let checkpoint = checkpoint();
+        let path = temporary_path("round_trip");
+        checkpoint.save(&path).unwrap();
+        let loaded = Checkpoint::load(&path);
+        fs::remove_file(&path).unwrap();
+
+        let loaded = loaded.unwrap();
+        assert_eq!(loaded.settings, checkpoint.settings);
+        assert_eq!(loaded.samples, checkpoint.samples);
+        assert_eq!(bytes(&loaded), bytes(&checkpoint));
+        assert!(loaded.check(&checkpoint.settings).is_ok());
+    }
+
+    #[test]
+    fn truncated_checkpoint_is_invalid() {
+        let bytes = bytes(&checkpoint());
+        let path = temporary_path("truncated");
+        // Cut off within the header, and within the pixels
+        for length in [5, bytes.len() / 2, bytes.len() - 1] {
+            fs::write(&path, &bytes[..length]).unwrap();
+            let loaded = Checkpoint::load(&path);
+            assert!(
+                matches!(loaded, Err(CheckpointError::Invalid { .. })),
+                "loading {length} bytes gave {loaded:?}"
+            );
+        }
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn wrong_magic_is_invalid() {
+        let mut bytes = bytes(&checkpoint());
+        bytes[0] = b'X';
+        let error = Checkpoint::read(&mut bytes.as_slice()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn check_catches_a_changed_scene_and_wrong_sizes() {
+        let mut checkpoint = checkpoint();
+        let settings = checkpoint.settings;
+        assert!(matches!(
+            checkpoint.check(&CheckpointSettings {
+                scene: 1,
+                ..settings
+            }),
+            Err(CheckpointError::SceneChanged)
+        ));
+        checkpoint.statistics.pop();
+        assert!(matches!(
+            checkpoint.check(&settings),
+            Err(CheckpointError::WrongSize {
+                width: 3,
+                height: 2
+            })
+        ));
+    }
+
+    #[test]
+    fn total_samples_only_matter_for_stratified_sampling() {
+        let settings = checkpoint().settings;
+        let more = CheckpointSettings {
+            samples: 128,
+            ..settings
+        };
+        assert!(settings.check(&more).is_ok());
+
+        let stratified = CheckpointSettings {
+            sampler: SamplerKind::Stratified,
+            ..settings
+        };
+        assert!(matches!(
+            stratified.check(&CheckpointSettings {
+                samples: 128,
+                ..stratified
+            }),
+            Err(CheckpointError::Mismatch {
+                setting: "total samples",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn scene_hashes_change_with_the_files_read() {
+        let path = temporary_path("hashed_file");
+        fs::write(&path, "v 0 0 0").unwrap();
+        let hash = || {
+            let mut hasher = SceneHasher::default();
+            hasher.add(b"object; mesh; model.obj");
+            hasher.add_file(&path);
+            hasher.finish()
+        };
+        let before = hash();
+        assert_eq!(hash(), before);
+
+        let later = fs::metadata(&path).unwrap().modified().unwrap()
+            + std::time::Duration::from_secs(1);
+        File::options()
+            .write(true)
+            .open(&path)
+            .and_then(|file| file.set_modified(later))
+            .unwrap();
+        let after = hash();
+        fs::remove_file(&path).unwrap();
+        assert_ne!(after, before);
+    }
+}
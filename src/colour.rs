@@ -1,14 +1,29 @@
 use std::{fmt::Debug, ops::Mul};
 
+use glam::Vec3;
+use image::{Rgb, Rgb32FImage, RgbImage};
+
 use crate::{
     interval::Interval,
     textures::{solid_texture::SolidTexture, texture::Texture},
+    tone_mapping::ToneMapping,
 };
 
-use derive_more::{Add, Div, Mul as MulDerive, MulAssign, Sum};
+use derive_more::{Add, AddAssign, Div, Mul as MulDerive, MulAssign, Sub, Sum};
 
 #[derive(
-    Clone, Copy, Debug, Default, Add, Sum, MulDerive, MulAssign, Div, PartialEq,
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    Add,
+    AddAssign,
+    Sub,
+    Sum,
+    MulDerive,
+    MulAssign,
+    Div,
+    PartialEq,
 )]
 #[mul(forward)]
 #[mul_assign(forward)]
@@ -30,6 +45,30 @@ impl Mul<Colour> for f32 {
     }
 }
 
+impl From<Colour> for Rgb<f32> {
+    fn from(Colour(r, g, b): Colour) -> Self {
+        Rgb([r, g, b])
+    }
+}
+
+impl From<Rgb<f32>> for Colour {
+    fn from(Rgb([r, g, b]): Rgb<f32>) -> Self {
+        Colour(r, g, b)
+    }
+}
+
+impl From<Colour> for Vec3 {
+    fn from(Colour(r, g, b): Colour) -> Self {
+        Vec3::new(r, g, b)
+    }
+}
+
+impl From<Vec3> for Colour {
+    fn from(value: Vec3) -> Self {
+        Colour(value.x, value.y, value.z)
+    }
+}
+
 impl Colour {
     pub const WHITE: Self = Colour::new(1., 1., 1.);
     pub const BLACK: Self = Colour::new(0., 0., 0.);
@@ -45,15 +84,45 @@ impl Colour {
     pub fn lerp(self, rhs: Self, ratio: f32) -> Self {
         (1. - ratio) * self + ratio * rhs
     }
+
+    pub fn map(self, f: impl Fn(f32) -> f32) -> Self {
+        Self(f(self.0), f(self.1), f(self.2))
+    }
+
+    /// Relative luminance, with the Rec. 709 weights
+    pub fn luminance(self) -> f32 {
+        0.2126 * self.0 + 0.7152 * self.1 + 0.0722 * self.2
+    }
+}
+
+/// The sRGB transfer function, from linear to display encoded values
+fn srgb_encode(value: f32) -> f32 {
+    if value <= 0.003_130_8 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1. / 2.4) - 0.055
+    }
 }
 
 pub fn map_colours(colour: &Colour) -> (u8, u8, u8) {
-    let Colour(r, g, b) = *colour;
+    let Colour(r, g, b) = colour.map(srgb_encode);
     let colour_interval = Interval::new(0., 1.0);
     let (rbyte, gbyte, bbyte) = (
-        (colour_interval.clamp(r) * 255.) as u8,
-        (colour_interval.clamp(g) * 255.) as u8,
-        (colour_interval.clamp(b) * 255.) as u8,
+        (colour_interval.clamp(r) * 255.).round() as u8,
+        (colour_interval.clamp(g) * 255.).round() as u8,
+        (colour_interval.clamp(b) * 255.).round() as u8,
     );
     (rbyte, gbyte, bbyte)
 }
+
+/// Tone maps a linear framebuffer into an 8-bit sRGB image
+pub fn to_rgb_image(
+    image: &Rgb32FImage,
+    tone_mapping: &ToneMapping,
+) -> RgbImage {
+    RgbImage::from_fn(image.width(), image.height(), |i, j| {
+        let colour = tone_mapping.apply((*image.get_pixel(i, j)).into());
+        let (r, g, b) = map_colours(&colour);
+        Rgb([r, g, b])
+    })
+}
@@ -0,0 +1,129 @@
+use glam::Vec3;
+use image::{Rgb, Rgb32FImage};
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+use crate::film::Frame;
+
+/// Each pass doubles the gap between the taps it reads, so five passes
+/// reach 62 pixels either side
+const PASSES: u32 = 5;
+
+/// B3 spline weights of the 5 by 5 kernel, along each axis
+const KERNEL: [f32; 5] = [1. / 16., 1. / 4., 3. / 8., 1. / 4., 1. / 16.];
+
+/// How quickly weights fall with the difference in illumination, normal and
+/// albedo between a pixel and a tap
+const ILLUMINATION_SIGMA: f32 = 0.3;
+const NORMAL_SIGMA: f32 = 0.3;
+const ALBEDO_SIGMA: f32 = 0.1;
+
+/// Albedo below which a channel is left as it is, rather than dividing by
+/// nearly nothing
+const MIN_ALBEDO: f32 = 0.01;
+
+/// Smooths out noise with Dammertz et al.'s edge-avoiding à-trous wavelet
+/// filter, steered by the albedo and normal buffers. Texture detail is kept
+/// by filtering the illumination, the image divided by the albedo, and then
+/// multiplying the albedo back in.
+pub fn denoise(frame: &Frame) -> Rgb32FImage {
+    let (width, height) = frame.image.dimensions();
+    let pixels = |image: &Rgb32FImage| {
+        image
+            .pixels()
+            .map(|&Rgb(rgb)| Vec3::from(rgb))
+            .collect::<Vec<_>>()
+    };
+    let albedo = pixels(&frame.albedo)
+        .into_iter()
+        .map(|albedo| {
+            Vec3::select(
+                albedo.cmpgt(Vec3::splat(MIN_ALBEDO)),
+                albedo,
+                Vec3::ONE,
+            )
+        })
+        .collect::<Vec<_>>();
+    let normal = pixels(&frame.normal);
+    let mut illumination = pixels(&frame.image)
+        .into_iter()
+        .zip(&albedo)
+        .map(|(colour, albedo)| colour / *albedo)
+        .collect::<Vec<_>>();
+
+    let guide = Guide {
+        width: width as i64,
+        height: height as i64,
+        albedo: &albedo,
+        normal: &normal,
+    };
+    for pass in 0..PASSES {
+        illumination = guide.pass(&illumination, pass);
+    }
+
+    Rgb32FImage::from_fn(width, height, |x, y| {
+        let index = (y * width + x) as usize;
+        Rgb((illumination[index] * albedo[index]).to_array())
+    })
+}
+
+/// The buffers that keep the filter from blurring across edges
+struct Guide<'a> {
+    width: i64,
+    height: i64,
+    albedo: &'a [Vec3],
+    normal: &'a [Vec3],
+}
+
+impl Guide<'_> {
+    fn pass(&self, illumination: &[Vec3], pass: u32) -> Vec<Vec3> {
+        let step = 1 << pass;
+        // Later passes compare against smoother values, so can be stricter
+        let illumination_sigma = ILLUMINATION_SIGMA / (1 << pass) as f32;
+        let compressed = illumination
+            .iter()
+            .copied()
+            .map(compress)
+            .collect::<Vec<_>>();
+        (0..illumination.len())
+            .into_par_iter()
+            .map(|index| {
+                let (x, y) =
+                    (index as i64 % self.width, index as i64 / self.width);
+                let mut sum = Vec3::ZERO;
+                let mut total_weight = 0.;
+                for (dy, y_weight) in KERNEL.iter().enumerate() {
+                    let tap_y = y + (dy as i64 - 2) * step;
+                    if !(0..self.height).contains(&tap_y) {
+                        continue;
+                    }
+                    for (dx, x_weight) in KERNEL.iter().enumerate() {
+                        let tap_x = x + (dx as i64 - 2) * step;
+                        if !(0..self.width).contains(&tap_x) {
+                            continue;
+                        }
+                        let tap = (tap_y * self.width + tap_x) as usize;
+                        let distance = |values: &[Vec3], sigma: f32| {
+                            values[index].distance_squared(values[tap])
+                                / (sigma * sigma)
+                        };
+                        let weight = x_weight
+                            * y_weight
+                            * (-distance(&compressed, illumination_sigma)
+                                - distance(self.normal, NORMAL_SIGMA)
+                                - distance(self.albedo, ALBEDO_SIGMA))
+                            .exp();
+                        sum += weight * illumination[tap];
+                        total_weight += weight;
+                    }
+                }
+                sum / total_weight
+            })
+            .collect()
+    }
+}
+
+/// Squashes radiance roughly as the eye does, so differences between
+/// bright values count for less than between dark ones
+fn compress(value: Vec3) -> Vec3 {
+    value.max(Vec3::ZERO).powf(0.5)
+}
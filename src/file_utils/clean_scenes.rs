@@ -8,25 +8,85 @@ pub(super) fn clean_scene(scene: &str) -> String {
 }
 
 mod syntax_cleaner {
+    use std::ops::Range;
+
+    /// Where the file path of a mesh or image row is, so that it can be
+    /// left as written. Paths are read from the scene verbatim, and changing
+    /// their case or spacing would point them at a different file.
+    fn path_field(line: &str) -> Option<Range<usize>> {
+        let key = |section: &str| {
+            let section = section
+                .split_whitespace()
+                .collect::<String>()
+                .to_ascii_lowercase();
+            section
+                .strip_prefix("type=")
+                .map_or(section.clone(), str::to_owned)
+        };
+        let sections = line.split(';').map(key).collect::<Vec<_>>();
+        let has_path = match sections.first()?.as_str() {
+            "object" => sections.get(1).is_some_and(|kind| kind == "mesh"),
+            "group" => sections.get(3).is_some_and(|kind| kind == "mesh"),
+            "texture" => sections.get(2).is_some_and(|kind| kind == "image"),
+            _ => false,
+        };
+        if !has_path || sections.len() < 3 {
+            return None;
+        }
+        let start = line.rfind(';')? + 1;
+        let field = line[start..].split(',').next()?;
+        let trimmed = field.trim_start();
+        let start = start + field.len() - trimmed.len();
+        Some(start..start + trimmed.trim_end().len())
+    }
+
+    /// Applies `clean` to a line on either side of its path, if it has one
+    fn around_path(line: &str, clean: impl Fn(&str) -> String) -> String {
+        match path_field(line) {
+            Some(path) => format!(
+                "{}{}{}",
+                clean(&line[..path.start]),
+                &line[path.clone()],
+                clean(&line[path.end..])
+            ),
+            None => clean(line),
+        }
+    }
+
+    fn by_line(scene: &str, clean: impl Fn(&str) -> String) -> String {
+        scene.lines().map(clean).collect::<Vec<_>>().join("\n")
+    }
+
     pub(super) fn lowercase(scene: &str) -> String {
-        scene.to_ascii_lowercase()
+        by_line(scene, |line| around_path(line, str::to_ascii_lowercase))
     }
 
     const PUNCTUATION_MARKS: [&str; 3] = [";", ",", "//"];
 
     pub(super) fn split_punctuation(scene: String) -> String {
-        PUNCTUATION_MARKS.into_iter().fold(scene, |scene, mark| {
-            let mark_space = &format!("{mark} ");
-            scene.replace(mark, mark_space)
-        })
+        let split = |text: &str| {
+            PUNCTUATION_MARKS
+                .into_iter()
+                .fold(text.to_owned(), |text, mark| {
+                    let mark_space = &format!("{mark} ");
+                    text.replace(mark, mark_space)
+                })
+        };
+        by_line(&scene, |line| around_path(line, split))
     }
 
     pub(super) fn clean_whitespace(mut scene: String) -> String {
-        scene = scene
-            .lines()
-            .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
-            .collect::<Vec<_>>()
-            .join("\n");
+        let words =
+            |text: &str| text.split_whitespace().collect::<Vec<_>>().join(" ");
+        scene = by_line(&scene, |line| match path_field(line) {
+            Some(path) => format!(
+                "{} {}{}",
+                words(&line[..path.start]),
+                &line[path.clone()],
+                words(&line[path.end..])
+            ),
+            None => words(line),
+        });
 
         let mut changed = scene.replace("\n\n\n", "\n\n");
         while scene != changed {
@@ -38,10 +98,11 @@ mod syntax_cleaner {
 }
 
 mod order_scenes {
-    use std::{collections::HashMap, fmt::Write};
+    use std::collections::HashMap;
 
-    const LINE_TYPES: [&str; 5] =
-        ["point", "colour", "texture", "material", "object"];
+    const LINE_TYPES: [&str; 7] = [
+        "point", "colour", "texture", "material", "object", "group", "instance",
+    ];
 
     pub(super) fn order_lines(scene: &str) -> String {
         let lines = scene.lines().collect::<Vec<_>>();
@@ -50,7 +111,11 @@ mod order_scenes {
 
         let lines = other_lines
             .iter()
-            .map(|line| line.split_once(';').unwrap_or((line, "")))
+            .map(|line| {
+                let line_type =
+                    line.split_once(';').map_or(*line, |split| split.0);
+                (line_type, *line)
+            })
             .collect::<Vec<_>>();
 
         let ordered_scene =
@@ -73,15 +138,56 @@ mod order_scenes {
         let mut sorted = lines.into_iter();
         let mut output = String::new();
 
-        for (line_type, _) in to_sort {
+        for (line_type, line) in to_sort {
+            // Rows are rewritten whole, as spacing is tidied up afterwards
             if LINE_TYPES.contains(line_type) {
-                let (line_type, line_content) = sorted.next().unwrap();
-                let _ = write!(output, "{line_type}; {line_content}");
+                let (_, sorted_line) = sorted.next().unwrap();
+                output += sorted_line;
             } else {
-                output += line_type;
+                output += line;
             }
             output.push('\n');
         }
         output
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clean_row(row: &str) -> String {
+        let scene = format!("Camera; 0, 0, 0\nSky; 1, 1, 1\n\n{row}\n");
+        let cleaned = clean_scene(&scene);
+        assert_eq!(clean_scene(&cleaned), cleaned);
+        cleaned.lines().nth(3).unwrap().to_owned()
+    }
+
+    #[test]
+    fn mesh_paths_are_left_as_written() {
+        assert_eq!(
+            clean_row("Object;MESH;  Models/My Teapot.OBJ ,Red"),
+            "object; mesh; Models/My Teapot.OBJ, red"
+        );
+        assert_eq!(
+            clean_row("group;Pots;object;mesh;Models/Teapot.obj"),
+            "group; pots; object; mesh; Models/Teapot.obj"
+        );
+    }
+
+    #[test]
+    fn image_paths_are_left_as_written() {
+        assert_eq!(
+            clean_row("texture;Wood;Image;Textures/Oak  Floor.PNG,Repeat"),
+            "texture; wood; image; Textures/Oak  Floor.PNG, repeat"
+        );
+    }
+
+    #[test]
+    fn other_rows_are_lowercased() {
+        assert_eq!(
+            clean_row("Texture;Mesh;Solid;Red"),
+            "texture; mesh; solid; red"
+        );
+    }
+}
@@ -0,0 +1,39 @@
+use std::fmt::Write;
+
+/// Lists the lines removed from `original` and added in `changed`, each with
+/// its line number, based on their longest common subsequence
+pub fn line_diff(original: &str, changed: &str) -> String {
+    let old = original.lines().collect::<Vec<_>>();
+    let new = changed.lines().collect::<Vec<_>>();
+
+    // common[i][j] is the length of the longest common subsequence of
+    // old[i..] and new[j..]
+    let mut common = vec![vec![0u32; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i][j] = if old[i] == new[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let mut output = String::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            i += 1;
+            j += 1;
+        } else if j == new.len()
+            || (i < old.len() && common[i + 1][j] >= common[i][j + 1])
+        {
+            let _ = writeln!(output, "{:>5} - {}", i + 1, old[i]);
+            i += 1;
+        } else {
+            let _ = writeln!(output, "{:>5} + {}", j + 1, new[j]);
+            j += 1;
+        }
+    }
+    output
+}
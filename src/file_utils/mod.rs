@@ -1,18 +1,67 @@
-use std::fs::read_dir;
+use std::{
+    fs::{self, read_dir},
+    io,
+    path::{Path, PathBuf},
+};
 
 use crate::file_utils::clean_scenes::clean_scene;
 
 mod clean_scenes;
+mod diff;
 
-pub fn clean_scenes() {
-    for path in read_dir("scenes").unwrap() {
-        let path = path.unwrap().path();
-        let file = std::fs::read(&path).unwrap();
+pub use diff::line_diff;
+
+/// A scene file whose formatting differs from the canonical one
+#[derive(Debug)]
+pub struct UncleanScene {
+    pub path: PathBuf,
+    pub original: String,
+    pub cleaned: String,
+}
+
+/// Every `.scene` file in `scenes_dir`, sorted by path
+pub fn scene_paths(scenes_dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    for entry in read_dir(scenes_dir)? {
+        let path = entry?.path();
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "scene")
+        {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+/// Finds the scenes in `scenes_dir` that formatting would change, without
+/// touching any of them
+pub fn find_unclean_scenes(scenes_dir: &Path) -> io::Result<Vec<UncleanScene>> {
+    let mut unclean = Vec::new();
+    for path in scene_paths(scenes_dir)? {
+        let file = fs::read(&path)?;
         if file.is_empty() {
             continue;
         }
-        let cleaned =
-            clean_scene(&file.into_iter().map(char::from).collect::<String>());
-        std::fs::write(path, cleaned).unwrap();
+        let original = file.into_iter().map(char::from).collect::<String>();
+        let cleaned = clean_scene(&original);
+        if cleaned != original {
+            unclean.push(UncleanScene {
+                path,
+                original,
+                cleaned,
+            });
+        }
+    }
+    Ok(unclean)
+}
+
+/// Formats every scene in `scenes_dir`, returning the ones which changed
+pub fn clean_scenes(scenes_dir: &Path) -> io::Result<Vec<UncleanScene>> {
+    let unclean = find_unclean_scenes(scenes_dir)?;
+    for scene in &unclean {
+        fs::write(&scene.path, &scene.cleaned)?;
     }
+    Ok(unclean)
 }
@@ -0,0 +1,400 @@
+use std::io::{self, Read, Write};
+
+use glam::{Vec2, Vec3};
+use image::{Rgb, Rgb32FImage};
+
+use crate::{
+    checkpoint::{read_floats, read_words, write_floats, write_words},
+    colour::Colour,
+    filter::Filter,
+};
+
+/// What a camera ray first saw, which guides denoising and fills the
+/// arbitrary output variables
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Features {
+    /// The surface's colour, or the sky's
+    pub albedo: Colour,
+    /// The surface's normal, or zero for the sky
+    pub normal: Vec3,
+    /// Distance to the surface, or zero for the sky
+    pub depth: f32,
+    pub uv: Vec2,
+    /// One more than the surface's position in the scene, or zero for the
+    /// sky
+    pub object: u32,
+    /// The surface's material number, or zero if it has none
+    pub material: u32,
+    /// How many surfaces the whole path hit
+    pub hits: u32,
+}
+
+/// A finished render, along with the features seen through each pixel.
+/// Object and material numbers are those of the pixel's first sample, and
+/// the other features are averaged over all of its samples.
+#[derive(Clone, Debug)]
+pub struct Frame {
+    pub image: Rgb32FImage,
+    pub albedo: Rgb32FImage,
+    pub normal: Rgb32FImage,
+    pub depth: Rgb32FImage,
+    /// u and v in the red and green channels
+    pub uv: Rgb32FImage,
+    pub object: Rgb32FImage,
+    pub material: Rgb32FImage,
+    pub hits: Rgb32FImage,
+}
+
+/// The features recorded at one pixel
+#[derive(Clone, Copy, Debug, Default)]
+struct FeatureSums {
+    samples: u32,
+    albedo: Colour,
+    normal: Vec3,
+    depth: f32,
+    uv: Vec2,
+    hits: u32,
+    object: u32,
+    material: u32,
+}
+
+impl FeatureSums {
+    fn add(&mut self, features: Features) {
+        if self.samples == 0 {
+            self.object = features.object;
+            self.material = features.material;
+        }
+        self.samples += 1;
+        self.albedo += features.albedo;
+        self.normal += features.normal;
+        self.depth += features.depth;
+        self.uv += features.uv;
+        self.hits += features.hits;
+    }
+
+    fn merge(&mut self, other: &Self) {
+        if self.samples == 0 {
+            self.object = other.object;
+            self.material = other.material;
+        }
+        self.samples += other.samples;
+        self.albedo += other.albedo;
+        self.normal += other.normal;
+        self.depth += other.depth;
+        self.uv += other.uv;
+        self.hits += other.hits;
+    }
+
+    fn scale(&self) -> f32 {
+        1. / self.samples.max(1) as f32
+    }
+
+    fn write(&self, out: &mut impl Write) -> io::Result<()> {
+        write_words(
+            out,
+            &[self.samples, self.hits, self.object, self.material],
+        )?;
+        let (albedo, normal) = (Vec3::from(self.albedo), self.normal);
+        write_floats(
+            out,
+            &[
+                albedo.x, albedo.y, albedo.z, normal.x, normal.y, normal.z,
+                self.depth, self.uv.x, self.uv.y,
+            ],
+        )
+    }
+
+    fn read(input: &mut impl Read) -> io::Result<Self> {
+        let [samples, hits, object, material] = read_words(input)?;
+        let [r, g, b, x, y, z, depth, u, v] = read_floats(input)?;
+        Ok(Self {
+            samples,
+            albedo: Colour::new(r, g, b),
+            normal: Vec3::new(x, y, z),
+            depth,
+            uv: Vec2::new(u, v),
+            hits,
+            object,
+            material,
+        })
+    }
+}
+
+/// Filter weighted sums of the samples landing on a rectangle of an image,
+/// which may reach past the pixels rendered into it so that neighbouring
+/// films can be merged. Features are only kept for the pixel each sample
+/// was taken in, as is an unweighted sum of those samples, which stands in
+/// for the filtered value where negative lobes leave no weight.
+#[derive(Clone, Debug)]
+pub struct Film {
+    left: i64,
+    top: i64,
+    width: u32,
+    height: u32,
+    sums: Vec<Colour>,
+    weights: Vec<f32>,
+    box_sums: Vec<Colour>,
+    box_counts: Vec<u32>,
+    features: Vec<FeatureSums>,
+}
+
+impl Film {
+    /// A film covering `width` by `height` pixels, starting from the pixel
+    /// at `left`, `top`
+    pub fn new(left: i64, top: i64, width: u32, height: u32) -> Self {
+        let pixel_count = (width * height) as usize;
+        Self {
+            left,
+            top,
+            width,
+            height,
+            sums: vec![Colour::BLACK; pixel_count],
+            weights: vec![0.; pixel_count],
+            box_sums: vec![Colour::BLACK; pixel_count],
+            box_counts: vec![0; pixel_count],
+            features: vec![FeatureSums::default(); pixel_count],
+        }
+    }
+
+    /// The width and height of the film in pixels
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    fn right(&self) -> i64 {
+        self.left + i64::from(self.width)
+    }
+
+    fn bottom(&self) -> i64 {
+        self.top + i64::from(self.height)
+    }
+
+    fn contains(&self, x: i64, y: i64) -> bool {
+        (self.left..self.right()).contains(&x)
+            && (self.top..self.bottom()).contains(&y)
+    }
+
+    fn index(&self, x: i64, y: i64) -> usize {
+        ((y - self.top) * i64::from(self.width) + x - self.left) as usize
+    }
+
+    /// Adds a sample of `colour`, taken `offset` from the centre of
+    /// `pixel`, to each pixel `filter` reaches. The offset is kept apart
+    /// from the pixel so that rounding can't move the sample across a
+    /// pixel's edge.
+    pub fn splat(
+        &mut self,
+        (i, j): (u32, u32),
+        offset: Vec2,
+        colour: Colour,
+        filter: &Filter,
+    ) {
+        let (i, j) = (i64::from(i), i64::from(j));
+        if self.contains(i, j) {
+            let index = self.index(i, j);
+            self.box_sums[index] += colour;
+            self.box_counts[index] += 1;
+        }
+        let low = (offset - filter.radius).ceil();
+        let high = (offset + filter.radius).floor();
+        let rows = (j + low.y as i64).max(self.top)
+            ..(j + high.y as i64 + 1).min(self.bottom());
+        let columns = (i + low.x as i64).max(self.left)
+            ..(i + high.x as i64 + 1).min(self.right());
+        for y in rows {
+            for x in columns.clone() {
+                let to_pixel = Vec2::new((x - i) as f32, (y - j) as f32);
+                let weight = filter.weight(to_pixel - offset);
+                if weight == 0. {
+                    continue;
+                }
+                let index = self.index(x, y);
+                self.sums[index] += weight * colour;
+                self.weights[index] += weight;
+            }
+        }
+    }
+
+    /// Adds the features a sample taken in `pixel` saw
+    pub fn record(&mut self, (i, j): (u32, u32), features: Features) {
+        let index = self.index(i64::from(i), i64::from(j));
+        self.features[index].add(features);
+    }
+
+    /// Adds the sums of `other` where the two overlap
+    pub fn merge(&mut self, other: &Film) {
+        let rows = self.top.max(other.top)..self.bottom().min(other.bottom());
+        let columns =
+            self.left.max(other.left)..self.right().min(other.right());
+        for y in rows {
+            for x in columns.clone() {
+                let (index, other_index) =
+                    (self.index(x, y), other.index(x, y));
+                self.sums[index] += other.sums[other_index];
+                self.weights[index] += other.weights[other_index];
+                self.box_sums[index] += other.box_sums[other_index];
+                self.box_counts[index] += other.box_counts[other_index];
+                self.features[index].merge(&other.features[other_index]);
+            }
+        }
+    }
+
+    /// The weighted average at each pixel. Filters with negative lobes can
+    /// leave a pixel with no weight, where the average of the samples taken
+    /// in it is used instead, or black if there were none.
+    pub fn frame(&self) -> Frame {
+        let image = self.image_from(|index| {
+            let weight = self.weights[index];
+            if weight > 0. {
+                self.sums[index] * (1. / weight)
+            } else {
+                self.box_sums[index]
+                    * (1. / self.box_counts[index].max(1) as f32)
+            }
+        });
+        let feature = |value: fn(&FeatureSums) -> Colour| {
+            self.image_from(|index| value(&self.features[index]))
+        };
+        Frame {
+            image,
+            albedo: feature(|sums| sums.albedo * sums.scale()),
+            normal: feature(|sums| (sums.normal * sums.scale()).into()),
+            depth: feature(|sums| grey(sums.depth * sums.scale())),
+            uv: feature(|sums| {
+                let uv = sums.uv * sums.scale();
+                Colour::new(uv.x, uv.y, 0.)
+            }),
+            object: feature(|sums| grey(sums.object as f32)),
+            material: feature(|sums| grey(sums.material as f32)),
+            hits: feature(|sums| grey(sums.hits as f32 * sums.scale())),
+        }
+    }
+
+    /// Writes the sums at each pixel, for a checkpoint
+    pub(crate) fn write(&self, out: &mut impl Write) -> io::Result<()> {
+        for index in 0..self.sums.len() {
+            let sum = Vec3::from(self.sums[index]);
+            let box_sum = Vec3::from(self.box_sums[index]);
+            write_floats(
+                out,
+                &[
+                    sum.x,
+                    sum.y,
+                    sum.z,
+                    self.weights[index],
+                    box_sum.x,
+                    box_sum.y,
+                    box_sum.z,
+                ],
+            )?;
+            write_words(out, &[self.box_counts[index]])?;
+            self.features[index].write(out)?;
+        }
+        Ok(())
+    }
+
+    /// Reads the sums a checkpoint holds for a whole image
+    pub(crate) fn read(
+        input: &mut impl Read,
+        width: u32,
+        height: u32,
+    ) -> io::Result<Self> {
+        let mut film = Self::new(0, 0, width, height);
+        for index in 0..film.sums.len() {
+            let [r, g, b, weight, box_r, box_g, box_b] = read_floats(input)?;
+            let [box_count] = read_words(input)?;
+            film.sums[index] = Colour::new(r, g, b);
+            film.weights[index] = weight;
+            film.box_sums[index] = Colour::new(box_r, box_g, box_b);
+            film.box_counts[index] = box_count;
+            film.features[index] = FeatureSums::read(input)?;
+        }
+        Ok(film)
+    }
+
+    fn image_from(&self, colour: impl Fn(usize) -> Colour) -> Rgb32FImage {
+        Rgb32FImage::from_fn(self.width, self.height, |x, y| {
+            let index =
+                self.index(self.left + i64::from(x), self.top + i64::from(y));
+            Rgb::from(colour(index))
+        })
+    }
+}
+
+fn grey(value: f32) -> Colour {
+    Colour::new(value, value, value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::FilterKind;
+
+    fn film() -> Film {
+        let mut film = Film::new(0, 0, 3, 2);
+        let filter = Filter::new(FilterKind::Tent, None);
+        film.splat(
+            (0, 0),
+            Vec2::new(0.2, -0.1),
+            Colour::new(1., 0.5, 0.),
+            &filter,
+        );
+        film.splat((2, 1), Vec2::ZERO, Colour::new(0., 0.25, 2.), &filter);
+        film.record(
+            (1, 1),
+            Features {
+                albedo: Colour::new(0.3, 0.6, 0.9),
+                normal: Vec3::Y,
+                depth: 4.5,
+                uv: Vec2::new(0.25, 0.75),
+                object: 2,
+                material: 3,
+                hits: 5,
+            },
+        );
+        film
+    }
+
+    #[test]
+    fn write_then_read_gives_the_same_frame() {
+        let film = film();
+        let mut bytes = Vec::new();
+        film.write(&mut bytes).unwrap();
+        let read = Film::read(&mut bytes.as_slice(), 3, 2).unwrap();
+
+        let (written, read) = (film.frame(), read.frame());
+        assert_eq!(written.image, read.image);
+        assert_eq!(written.albedo, read.albedo);
+        assert_eq!(written.normal, read.normal);
+        assert_eq!(written.depth, read.depth);
+        assert_eq!(written.uv, read.uv);
+        assert_eq!(written.object, read.object);
+        assert_eq!(written.material, read.material);
+        assert_eq!(written.hits, read.hits);
+    }
+
+    #[test]
+    fn pixels_left_without_weight_average_their_own_samples() {
+        let mut film = Film::new(0, 0, 2, 1);
+        let filter = Filter::new(FilterKind::Lanczos, None);
+        let own = Colour::new(0.25, 0.5, 0.75);
+        film.splat((1, 0), Vec2::new(0.45, 0.45), own, &filter);
+        // Each lands 1.4 pixels away, in the first negative lobe
+        for _ in 0..4 {
+            film.splat((0, 0), Vec2::new(-0.4, 0.), Colour::WHITE, &filter);
+        }
+        assert!(film.weights[1] <= 0.);
+
+        let frame = film.frame();
+        assert_eq!(frame.image.get_pixel(1, 0), &Rgb::from(own));
+    }
+
+    #[test]
+    fn read_stops_at_the_end_of_the_input() {
+        let mut bytes = Vec::new();
+        film().write(&mut bytes).unwrap();
+        bytes.truncate(bytes.len() - 1);
+        let error = Film::read(&mut bytes.as_slice(), 3, 2).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
@@ -0,0 +1,114 @@
+use std::f32::consts::PI;
+
+use clap::ValueEnum;
+use glam::Vec2;
+use serde::Deserialize;
+
+/// Shape of the weight a sample gives to each pixel around it
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum FilterKind {
+    /// Equal weight within the radius, which with the default radius only
+    /// reaches the pixel the sample is in
+    #[default]
+    Box,
+    /// Weight falling linearly to zero at the radius
+    Tent,
+    /// A Gaussian with a standard deviation of a third of the radius,
+    /// shifted down to reach zero at the radius
+    Gaussian,
+    /// Mitchell and Netravali's cubic, with B = C = 1/3
+    Mitchell,
+    /// A sinc windowed by a wider sinc, with as many lobes as the radius
+    Lanczos,
+}
+
+impl FilterKind {
+    /// The radius each filter is designed around, in pixels
+    pub fn default_radius(self) -> f32 {
+        match self {
+            Self::Box => 0.5,
+            Self::Tent => 1.,
+            Self::Gaussian => 1.5,
+            Self::Mitchell => 2.,
+            Self::Lanczos => 3.,
+        }
+    }
+}
+
+/// A separable reconstruction filter, weighing samples by their offset from
+/// a pixel's centre
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Filter {
+    pub kind: FilterKind,
+    pub radius: f32,
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        Self::new(FilterKind::default(), None)
+    }
+}
+
+impl Filter {
+    /// A filter of `kind` with `radius`, or the kind's own radius if that
+    /// isn't given
+    pub fn new(kind: FilterKind, radius: Option<f32>) -> Self {
+        Self {
+            kind,
+            radius: radius.unwrap_or(kind.default_radius()).max(f32::EPSILON),
+        }
+    }
+
+    pub fn weight(&self, offset: Vec2) -> f32 {
+        self.weight_1d(offset.x) * self.weight_1d(offset.y)
+    }
+
+    fn weight_1d(&self, offset: f32) -> f32 {
+        let radius = self.radius;
+        // Half open, so the box filter's samples only land in one pixel
+        if offset <= -radius || offset > radius {
+            return 0.;
+        }
+        let offset = offset.abs();
+        match self.kind {
+            FilterKind::Box => 1.,
+            FilterKind::Tent => radius - offset,
+            FilterKind::Gaussian => {
+                let sigma = radius / 3.;
+                gaussian(offset, sigma) - gaussian(radius, sigma)
+            }
+            FilterKind::Mitchell => mitchell(2. * offset / radius),
+            FilterKind::Lanczos => sinc(offset) * sinc(offset / radius),
+        }
+    }
+}
+
+fn gaussian(x: f32, sigma: f32) -> f32 {
+    (-x * x / (2. * sigma * sigma)).exp()
+}
+
+/// The Mitchell-Netravali cubic over [0, 2], with B = C = 1/3
+fn mitchell(x: f32) -> f32 {
+    const B: f32 = 1. / 3.;
+    const C: f32 = 1. / 3.;
+    let weight = if x < 1. {
+        (12. - 9. * B - 6. * C) * x.powi(3)
+            + (-18. + 12. * B + 6. * C) * x.powi(2)
+            + (6. - 2. * B)
+    } else {
+        (-B - 6. * C) * x.powi(3)
+            + (6. * B + 30. * C) * x.powi(2)
+            + (-12. * B - 48. * C) * x
+            + (8. * B + 24. * C)
+    };
+    weight / 6.
+}
+
+/// The normalised sinc function
+fn sinc(x: f32) -> f32 {
+    if x.abs() < 1e-5 {
+        return 1.;
+    }
+    (PI * x).sin() / (PI * x)
+}
@@ -4,16 +4,17 @@ use derive_more::with_trait::{
     Add, AddAssign, Deref, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign,
     Sum,
 };
-use rand::{Rng, rng};
-use rand_distr::StandardNormal;
+use std::f32::consts::{FRAC_PI_4, TAU};
+
+use crate::sampler::{Sample, Sampler};
 
 pub trait NearZero {
     const EPSILON: f32 = 1e-8;
     fn near_zero(&self) -> bool;
 }
 pub trait VecRand {
-    fn rand_unit_vector() -> Self;
-    fn random_on_unit_disk() -> Self;
+    fn rand_unit_vector(sampler: &mut Sampler) -> Self;
+    fn random_on_unit_disk(sampler: &mut Sampler) -> Self;
 }
 
 impl NearZero for Vec3 {
@@ -25,21 +26,27 @@ impl NearZero for Vec3 {
 }
 
 impl VecRand for Vec3 {
-    fn rand_unit_vector() -> Self {
-        let mut rng = rng();
-        let (x, y, z) = (
-            rng.sample(StandardNormal),
-            rng.sample(StandardNormal),
-            rng.sample(StandardNormal),
-        );
-        Vec3::new(x, y, z)
+    fn rand_unit_vector(sampler: &mut Sampler) -> Self {
+        let sample = sampler.get_2d();
+        let z = 1. - 2. * sample.x;
+        let radius = (1. - z * z).max(0.).sqrt();
+        let (sin_phi, cos_phi) = (TAU * sample.y).sin_cos();
+        Vec3::new(radius * cos_phi, radius * sin_phi, z)
     }
 
-    fn random_on_unit_disk() -> Self {
-        let mut rng = rng();
-        let (x, y) = (rng.sample(StandardNormal), rng.sample(StandardNormal));
-
-        Vec3::new(x, y, 0.)
+    fn random_on_unit_disk(sampler: &mut Sampler) -> Self {
+        // Shirley and Chiu's concentric mapping, which keeps strata compact
+        let offset = 2. * sampler.get_2d() - Vec2::ONE;
+        if offset.near_zero() {
+            return Vec3::ZERO;
+        }
+        let (radius, theta) = if offset.x.abs() > offset.y.abs() {
+            (offset.x, FRAC_PI_4 * (offset.y / offset.x))
+        } else {
+            (offset.y, 2. * FRAC_PI_4 - FRAC_PI_4 * (offset.x / offset.y))
+        };
+        let (sin_theta, cos_theta) = theta.sin_cos();
+        Vec3::new(radius * cos_theta, radius * sin_theta, 0.)
     }
 }
 
@@ -99,6 +106,12 @@ impl From<Vec3> for Point3 {
     }
 }
 
+impl From<[f32; 3]> for Point3 {
+    fn from(value: [f32; 3]) -> Self {
+        Self(value.into())
+    }
+}
+
 impl Point3 {
     pub const fn new(x: f32, y: f32, z: f32) -> Self {
         Self(Vec3::new(x, y, z))
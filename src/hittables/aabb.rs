@@ -0,0 +1,150 @@
+use crate::{
+    geometry::{Point3, Ray},
+    interval::Interval,
+};
+
+use glam::Vec3;
+
+#[derive(Copy, Clone, Debug)]
+pub struct Aabb {
+    pub x: Interval,
+    pub y: Interval,
+    pub z: Interval,
+}
+
+impl Default for Aabb {
+    fn default() -> Self {
+        Self::EMPTY
+    }
+}
+
+impl Aabb {
+    pub const EMPTY: Self = Self {
+        x: Interval::EMPTY,
+        y: Interval::EMPTY,
+        z: Interval::EMPTY,
+    };
+
+    /// Box around objects such as planes that go on forever
+    pub const UNIVERSE: Self = Self {
+        x: Interval::UNIVERSE,
+        y: Interval::UNIVERSE,
+        z: Interval::UNIVERSE,
+    };
+
+    // Flat boxes (e.g. around axis-aligned triangles) would never be hit
+    // by the strict slab test, so every axis is given a minimum thickness
+    const MIN_THICKNESS: f32 = 1e-4;
+
+    pub fn new(x: Interval, y: Interval, z: Interval) -> Self {
+        let pad = |interval: Interval| {
+            if interval.size() < Self::MIN_THICKNESS {
+                interval.expand(Self::MIN_THICKNESS)
+            } else {
+                interval
+            }
+        };
+        Self {
+            x: pad(x),
+            y: pad(y),
+            z: pad(z),
+        }
+    }
+
+    pub fn from_points(points: &[Point3]) -> Self {
+        let mut intervals = [Interval::EMPTY; 3];
+        for point in points {
+            for (axis, interval) in intervals.iter_mut().enumerate() {
+                *interval =
+                    interval.union(Interval::new(point[axis], point[axis]));
+            }
+        }
+        let [x, y, z] = intervals;
+        Self::new(x, y, z)
+    }
+
+    pub fn axis(&self, axis: usize) -> Interval {
+        match axis {
+            0 => self.x,
+            1 => self.y,
+            _ => self.z,
+        }
+    }
+
+    pub fn union(self, other: Self) -> Self {
+        Self {
+            x: self.x.union(other.x),
+            y: self.y.union(other.y),
+            z: self.z.union(other.z),
+        }
+    }
+
+    /// Whether the box has finite extent along every axis
+    pub fn is_bounded(&self) -> bool {
+        [self.x, self.y, self.z].iter().all(|interval| {
+            interval.min.is_finite() && interval.max.is_finite()
+        })
+    }
+
+    pub fn corners(&self) -> [Point3; 8] {
+        std::array::from_fn(|corner| {
+            let pick = |bit: usize, interval: Interval| {
+                if corner & bit == 0 {
+                    interval.min
+                } else {
+                    interval.max
+                }
+            };
+            Point3::new(pick(1, self.x), pick(2, self.y), pick(4, self.z))
+        })
+    }
+
+    pub fn centroid(&self) -> Point3 {
+        Point3::new(
+            f32::midpoint(self.x.min, self.x.max),
+            f32::midpoint(self.y.min, self.y.max),
+            f32::midpoint(self.z.min, self.z.max),
+        )
+    }
+
+    pub fn surface_area(&self) -> f32 {
+        let (dx, dy, dz) = (self.x.size(), self.y.size(), self.z.size());
+        if dx < 0. || dy < 0. || dz < 0. {
+            return 0.;
+        }
+        2. * (dx * dy + dy * dz + dz * dx)
+    }
+
+    pub fn longest_axis(&self) -> usize {
+        let sizes = [self.x.size(), self.y.size(), self.z.size()];
+        if sizes[0] > sizes[1] && sizes[0] > sizes[2] {
+            0
+        } else if sizes[1] > sizes[2] {
+            1
+        } else {
+            2
+        }
+    }
+
+    /// Slab test, returning the time the ray enters the box
+    pub fn hit(
+        &self,
+        ray: Ray,
+        inverse_direction: Vec3,
+        interval: Interval,
+    ) -> Option<f32> {
+        let (mut t_min, mut t_max) = (interval.min, interval.max);
+        for axis in 0..=2 {
+            let bounds = self.axis(axis);
+            let t0 = (bounds.min - ray.origin[axis]) * inverse_direction[axis];
+            let t1 = (bounds.max - ray.origin[axis]) * inverse_direction[axis];
+            let (t0, t1) = if t0 < t1 { (t0, t1) } else { (t1, t0) };
+            t_min = t_min.max(t0);
+            t_max = t_max.min(t1);
+            if t_max < t_min {
+                return None;
+            }
+        }
+        Some(t_min)
+    }
+}
@@ -0,0 +1,237 @@
+use crate::{
+    geometry::{Point3, Ray},
+    hittables::{aabb::Aabb, hittable::HitRecord},
+    interval::Interval,
+};
+
+#[derive(Copy, Clone, Debug)]
+enum NodeKind {
+    Leaf { first: u32, count: u32 },
+    // The first child always directly follows its parent in the node array
+    Interior { second_child: u32, axis: u8 },
+}
+
+#[derive(Copy, Clone, Debug)]
+struct BvhNode {
+    bounds: Aabb,
+    kind: NodeKind,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct BuildPrimitive {
+    index: usize,
+    bounds: Aabb,
+    centroid: Point3,
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+struct Bucket {
+    count: usize,
+    bounds: Aabb,
+}
+
+/// Bounding volume hierarchy built with the surface area heuristic and
+/// flattened into depth-first order
+#[derive(Clone, Debug, Default)]
+pub struct Bvh {
+    nodes: Vec<BvhNode>,
+}
+
+impl Bvh {
+    const BUCKET_COUNT: usize = 12;
+    const MAX_LEAF_SIZE: usize = 4;
+    // Cost of testing a node's box relative to intersecting a primitive
+    const TRAVERSAL_COST: f32 = 0.125;
+
+    /// Builds a hierarchy over `items`, reordering them so that every leaf
+    /// refers to a contiguous range
+    pub fn build<T>(
+        items: &mut Vec<T>,
+        bounds_of: impl Fn(&T) -> Aabb,
+    ) -> Self {
+        let mut primitives = items
+            .iter()
+            .enumerate()
+            .map(|(index, item)| {
+                let bounds = bounds_of(item);
+                BuildPrimitive {
+                    index,
+                    bounds,
+                    centroid: bounds.centroid(),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let mut nodes = Vec::with_capacity(2 * primitives.len());
+        if !primitives.is_empty() {
+            Self::build_node(&mut primitives, 0, &mut nodes);
+        }
+
+        let mut slots = items.drain(..).map(Some).collect::<Vec<_>>();
+        items.extend(
+            primitives
+                .iter()
+                .map(|primitive| slots[primitive.index].take().unwrap()),
+        );
+        Self { nodes }
+    }
+
+    pub fn bounding_box(&self) -> Aabb {
+        self.nodes.first().map_or(Aabb::EMPTY, |node| node.bounds)
+    }
+
+    fn build_node(
+        primitives: &mut [BuildPrimitive],
+        offset: usize,
+        nodes: &mut Vec<BvhNode>,
+    ) {
+        let bounds =
+            primitives.iter().fold(Aabb::EMPTY, |bounds, primitive| {
+                bounds.union(primitive.bounds)
+            });
+        let node_index = nodes.len();
+        let leaf = BvhNode {
+            bounds,
+            kind: NodeKind::Leaf {
+                first: offset as u32,
+                count: primitives.len() as u32,
+            },
+        };
+        nodes.push(leaf);
+        if primitives.len() == 1 {
+            return;
+        }
+
+        let centroid_bounds = Aabb::from_points(
+            &primitives
+                .iter()
+                .map(|primitive| primitive.centroid)
+                .collect::<Vec<_>>(),
+        );
+        let axis = centroid_bounds.longest_axis();
+        let axis_extent = centroid_bounds.axis(axis);
+        let bucket_of = |primitive: &BuildPrimitive| {
+            let relative = (primitive.centroid[axis] - axis_extent.min)
+                / axis_extent.size();
+            ((relative * Self::BUCKET_COUNT as f32) as usize)
+                .min(Self::BUCKET_COUNT - 1)
+        };
+
+        let mut buckets = [Bucket::default(); Self::BUCKET_COUNT];
+        for primitive in primitives.iter() {
+            let bucket = &mut buckets[bucket_of(primitive)];
+            bucket.count += 1;
+            bucket.bounds = bucket.bounds.union(primitive.bounds);
+        }
+
+        let (best_split, best_cost) = (1..Self::BUCKET_COUNT)
+            .map(|split| {
+                let (below, above) = buckets.split_at(split);
+                let side_cost = |side: &[Bucket]| {
+                    let merged =
+                        side.iter().fold(Bucket::default(), |a, b| Bucket {
+                            count: a.count + b.count,
+                            bounds: a.bounds.union(b.bounds),
+                        });
+                    merged.count as f32 * merged.bounds.surface_area()
+                };
+                let cost = Self::TRAVERSAL_COST
+                    + (side_cost(below) + side_cost(above))
+                        / bounds.surface_area();
+                (split, cost)
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .unwrap();
+
+        if primitives.len() <= Self::MAX_LEAF_SIZE
+            && best_cost >= primitives.len() as f32
+        {
+            return;
+        }
+
+        let mut middle = 0;
+        for index in 0..primitives.len() {
+            if bucket_of(&primitives[index]) < best_split {
+                primitives.swap(index, middle);
+                middle += 1;
+            }
+        }
+        // Coincident centroids can't be separated by buckets, so fall back
+        // to splitting the primitives evenly
+        if middle == 0 || middle == primitives.len() {
+            middle = primitives.len() / 2;
+            primitives.select_nth_unstable_by(middle, |a, b| {
+                a.centroid[axis].total_cmp(&b.centroid[axis])
+            });
+        }
+
+        let (below, above) = primitives.split_at_mut(middle);
+        Self::build_node(below, offset, nodes);
+        let second_child = nodes.len() as u32;
+        Self::build_node(above, offset + middle, nodes);
+        nodes[node_index].kind = NodeKind::Interior {
+            second_child,
+            axis: axis as u8,
+        };
+    }
+
+    /// Finds the closest hit, calling `hit_primitive` with the index of each
+    /// candidate and the interval still left to search
+    pub fn was_hit(
+        &self,
+        ray: Ray,
+        interval: Interval,
+        mut hit_primitive: impl FnMut(usize, Interval) -> Option<HitRecord>,
+    ) -> Option<HitRecord> {
+        if self.nodes.is_empty() {
+            return None;
+        }
+        let inverse_direction = ray.direction.recip();
+        let direction_negative = inverse_direction.to_array().map(|d| d < 0.);
+
+        let mut closest = interval.max;
+        let mut out_data = None;
+        let mut stack = Vec::with_capacity(32);
+        let mut current = 0;
+        loop {
+            let node = &self.nodes[current];
+            let search_interval = Interval::new(interval.min, closest);
+            if node
+                .bounds
+                .hit(ray, inverse_direction, search_interval)
+                .is_some()
+            {
+                match node.kind {
+                    NodeKind::Leaf { first, count } => {
+                        for index in first..first + count {
+                            if let Some(data) = hit_primitive(
+                                index as usize,
+                                Interval::new(interval.min, closest),
+                            ) {
+                                closest = data.collision_time;
+                                out_data = Some(data);
+                            }
+                        }
+                    }
+                    NodeKind::Interior { second_child, axis } => {
+                        // Visit the nearer child first so the far one can
+                        // be culled by a shorter interval
+                        let (near, far) = if direction_negative[axis as usize] {
+                            (second_child as usize, current + 1)
+                        } else {
+                            (current + 1, second_child as usize)
+                        };
+                        stack.push(far);
+                        current = near;
+                        continue;
+                    }
+                }
+            }
+            match stack.pop() {
+                Some(next) => current = next,
+                None => break,
+            }
+        }
+        out_data
+    }
+}
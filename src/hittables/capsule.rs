@@ -0,0 +1,153 @@
+use crate::{
+    geometry::{Point3, Ray},
+    hittables::{
+        aabb::Aabb,
+        hittable::{HitRecord, Hittable},
+        local::{Frame, LocalHit, around_axis, quadratic_roots},
+    },
+    interval::Interval,
+    textures::material::Material,
+};
+
+use glam::Vec3;
+
+/// Every point within a radius of a line segment: a cylinder with a
+/// hemisphere on each end. u runs around the axis and v along the whole
+/// length, ends included
+#[derive(Clone, Debug)]
+pub struct Capsule {
+    frame: Frame,
+    radius: f32,
+    length: f32,
+    material: Material,
+}
+
+impl Hittable for Capsule {
+    fn was_hit(&self, ray: Ray, interval: Interval) -> Option<HitRecord> {
+        let (origin, direction) = self.frame.ray_to_local(ray);
+        let radius_squared = self.radius * self.radius;
+        let side = quadratic_roots(
+            direction.truncate().length_squared(),
+            origin.truncate().dot(direction.truncate()),
+            origin.truncate().length_squared() - radius_squared,
+        )
+        .into_iter()
+        .flatten()
+        .filter_map(|time| {
+            let point = origin + time * direction;
+            (0. ..=self.length)
+                .contains(&point.z)
+                .then(|| self.local_hit(time, point, point.with_z(0.)))
+        });
+        // Only the outer half of each end's sphere is part of the surface
+        let ends = [(0., -1.), (self.length, 1.)].into_iter().flat_map(
+            |(height, facing): (f32, f32)| {
+                let centre = Vec3::new(0., 0., height);
+                let offset = origin - centre;
+                quadratic_roots(
+                    direction.length_squared(),
+                    offset.dot(direction),
+                    offset.length_squared() - radius_squared,
+                )
+                .into_iter()
+                .flatten()
+                .filter_map(move |time| {
+                    let point = origin + time * direction;
+                    ((point.z - height) * facing > 0.)
+                        .then(|| self.local_hit(time, point, point - centre))
+                })
+            },
+        );
+        self.frame.nearest(
+            ray,
+            interval,
+            side.chain(ends).map(Some),
+            &self.material,
+        )
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.frame.bounding_box(
+            Vec3::new(-self.radius, -self.radius, -self.radius),
+            Vec3::new(self.radius, self.radius, self.length + self.radius),
+        )
+    }
+}
+
+impl Capsule {
+    /// The capsule around the segment from `start` to `end`
+    pub fn new(
+        start: impl Into<Point3>,
+        end: impl Into<Point3>,
+        radius: f32,
+        material: impl Into<Material>,
+    ) -> Self {
+        let (start, end) = (start.into(), end.into());
+        let axis = *(end - start);
+        Self {
+            frame: Frame::new(start, axis),
+            radius,
+            length: axis.length(),
+            material: material.into(),
+        }
+    }
+
+    /// A hit at `point`, which is `outwards` from the nearest point on the
+    /// segment
+    fn local_hit(&self, time: f32, point: Vec3, outwards: Vec3) -> LocalHit {
+        LocalHit {
+            time,
+            normal: outwards / self.radius,
+            u: around_axis(point),
+            v: (point.z + self.radius) / (self.length + 2. * self.radius),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hit(origin: Point3, direction: Vec3) -> Option<HitRecord> {
+        let capsule = Capsule::new(
+            Point3::new(0., 0., 0.),
+            Point3::new(0., 2., 0.),
+            0.5,
+            Material::default(),
+        );
+        capsule.was_hit(
+            Ray::new(origin, direction),
+            Interval::new(0.001, f32::INFINITY),
+        )
+    }
+
+    fn assert_hit(hit: Option<HitRecord>, time: f32, normal: Vec3) {
+        let hit = hit.expect("the ray should hit the capsule");
+        assert!((hit.collision_time - time).abs() < 1e-4, "{hit:?}");
+        assert!(hit.normal_vector.abs_diff_eq(normal, 1e-4), "{hit:?}");
+    }
+
+    #[test]
+    fn hits_the_side() {
+        let hit = hit(Point3::new(5., 1., 0.), Vec3::NEG_X);
+        assert_hit(hit, 4.5, Vec3::X);
+    }
+
+    #[test]
+    fn hits_each_end() {
+        assert_hit(hit(Point3::new(0., 5., 0.), Vec3::NEG_Y), 2.5, Vec3::Y);
+        assert_hit(hit(Point3::new(0., -5., 0.), Vec3::Y), 4.5, Vec3::NEG_Y);
+    }
+
+    #[test]
+    fn hits_a_rounded_end_off_the_axis() {
+        // 0.3 across and 0.4 above the end of the segment
+        let hit = hit(Point3::new(0.3, 5., 0.), Vec3::NEG_Y);
+        assert_hit(hit, 2.6, Vec3::new(0.6, 0.8, 0.));
+    }
+
+    #[test]
+    fn misses_past_the_ends() {
+        assert!(hit(Point3::new(5., 2.6, 0.), Vec3::NEG_X).is_none());
+    }
+}
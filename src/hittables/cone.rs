@@ -0,0 +1,146 @@
+use crate::{
+    geometry::{Point3, Ray},
+    hittables::{
+        aabb::Aabb,
+        hittable::{HitRecord, Hittable},
+        local::{Frame, LocalHit, around_axis, cap_hit, quadratic_roots},
+    },
+    interval::Interval,
+    textures::material::Material,
+};
+
+use glam::Vec3;
+
+/// A cone narrowing from a circular base to a point, optionally closed off
+/// at the base. Around the side u runs around the axis and v up towards the
+/// apex, and on the base u runs around and v outwards
+#[derive(Clone, Debug)]
+pub struct Cone {
+    frame: Frame,
+    radius: f32,
+    height: f32,
+    capped: bool,
+    material: Material,
+}
+
+impl Hittable for Cone {
+    fn was_hit(&self, ray: Ray, interval: Interval) -> Option<HitRecord> {
+        let (origin, direction) = self.frame.ray_to_local(ray);
+        // The side is where the distance from the axis is `slope` times the
+        // distance below the apex
+        let slope = self.radius / self.height;
+        let slope_squared = slope * slope;
+        let below_apex = self.height - origin.z;
+        let side = quadratic_roots(
+            direction.truncate().length_squared()
+                - slope_squared * direction.z * direction.z,
+            origin.truncate().dot(direction.truncate())
+                + slope_squared * below_apex * direction.z,
+            origin.truncate().length_squared()
+                - slope_squared * below_apex * below_apex,
+        )
+        .into_iter()
+        .flatten()
+        .map(|time| {
+            let point = origin + time * direction;
+            (0. ..=self.height).contains(&point.z).then(|| {
+                let outwards = point.truncate().normalize_or_zero();
+                LocalHit {
+                    time,
+                    normal: outwards.extend(slope),
+                    u: around_axis(point),
+                    v: point.z / self.height,
+                }
+            })
+        });
+        let base = self
+            .capped
+            .then(|| cap_hit(origin, direction, 0., self.radius, -1.));
+        self.frame.nearest(
+            ray,
+            interval,
+            side.chain([base.flatten()]),
+            &self.material,
+        )
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.frame.bounding_box(
+            Vec3::new(-self.radius, -self.radius, 0.),
+            Vec3::new(self.radius, self.radius, self.height),
+        )
+    }
+}
+
+impl Cone {
+    /// The cone with a base of `radius` around `base` and its point at
+    /// `apex`
+    pub fn new(
+        base: impl Into<Point3>,
+        apex: impl Into<Point3>,
+        radius: f32,
+        capped: bool,
+        material: impl Into<Material>,
+    ) -> Self {
+        let (base, apex) = (base.into(), apex.into());
+        let axis = *(apex - base);
+        Self {
+            frame: Frame::new(base, axis),
+            radius,
+            height: axis.length(),
+            capped,
+            material: material.into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hit(capped: bool, origin: Point3, direction: Vec3) -> Option<HitRecord> {
+        let cone = Cone::new(
+            Point3::new(0., 0., 0.),
+            Point3::new(0., 2., 0.),
+            1.,
+            capped,
+            Material::default(),
+        );
+        cone.was_hit(
+            Ray::new(origin, direction),
+            Interval::new(0.001, f32::INFINITY),
+        )
+    }
+
+    fn assert_hit(hit: Option<HitRecord>, time: f32, normal: Vec3) {
+        let hit = hit.expect("the ray should hit the cone");
+        assert!((hit.collision_time - time).abs() < 1e-4, "{hit:?}");
+        assert!(hit.normal_vector.abs_diff_eq(normal, 1e-4), "{hit:?}");
+    }
+
+    #[test]
+    fn hits_the_side_with_a_slanted_normal() {
+        // Halfway up, the radius is halved
+        let hit = hit(true, Point3::new(5., 1., 0.), Vec3::NEG_X);
+        assert_hit(hit, 4.5, Vec3::new(2., 1., 0.).normalize());
+    }
+
+    #[test]
+    fn hits_the_base_when_capped() {
+        let hit = hit(true, Point3::new(0.2, -5., 0.), Vec3::Y);
+        assert_hit(hit.clone(), 5., Vec3::NEG_Y);
+        assert!(hit.unwrap().front_face);
+    }
+
+    #[test]
+    fn hits_the_inside_when_uncapped() {
+        let hit = hit(false, Point3::new(0.2, -5., 0.), Vec3::Y);
+        assert_hit(hit.clone(), 6.6, -Vec3::new(2., 1., 0.).normalize());
+        assert!(!hit.unwrap().front_face);
+    }
+
+    #[test]
+    fn misses_above_the_apex() {
+        assert!(hit(true, Point3::new(5., 2.5, 0.), Vec3::NEG_X).is_none());
+    }
+}
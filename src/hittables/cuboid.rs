@@ -0,0 +1,82 @@
+use crate::{
+    geometry::{Point3, Ray},
+    hittables::{
+        aabb::Aabb,
+        hittable::{HitRecord, Hittable},
+        quad::Quad,
+    },
+    interval::Interval,
+    textures::material::Material,
+};
+
+use glam::Vec3;
+
+/// An axis-aligned box made of six quads facing outwards, each with its own
+/// texture coordinates
+#[derive(Clone, Debug)]
+pub struct Cuboid {
+    // Six quads are a lot bigger than any other object, so they're kept
+    // out of line
+    faces: Box<[Quad; 6]>,
+    bounding_box: Aabb,
+}
+
+impl Hittable for Cuboid {
+    fn was_hit(&self, ray: Ray, interval: Interval) -> Option<HitRecord> {
+        let mut closest = interval.max;
+        let mut out_data = None;
+        for face in self.faces.iter() {
+            if let Some(data) =
+                face.was_hit(ray, Interval::new(interval.min, closest))
+            {
+                closest = data.collision_time;
+                out_data = Some(data);
+            }
+        }
+        out_data
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bounding_box
+    }
+}
+
+impl Cuboid {
+    /// The box with `corner` and `opposite_corner` at either end of a
+    /// diagonal
+    pub fn new(
+        corner: impl Into<Point3>,
+        opposite_corner: impl Into<Point3>,
+        material: impl Into<Material>,
+    ) -> Self {
+        let (corner, opposite_corner) = (corner.into(), opposite_corner.into());
+        let material = material.into();
+        let min = Point3::from(corner.min(*opposite_corner));
+        let max = Point3::from(corner.max(*opposite_corner));
+        let size = *(max - min);
+        let (dx, dy, dz) = (
+            Vec3::new(size.x, 0., 0.),
+            Vec3::new(0., size.y, 0.),
+            Vec3::new(0., 0., size.z),
+        );
+        let face = |corner: Point3, edge_u: Vec3, edge_v: Vec3| {
+            Quad::new(corner, edge_u, edge_v, material.clone())
+        };
+        Self {
+            faces: Box::new([
+                face(Point3::new(min.x, min.y, max.z), dx, dy),
+                face(Point3::new(max.x, min.y, max.z), -dz, dy),
+                face(Point3::new(max.x, min.y, min.z), -dx, dy),
+                face(min, dz, dy),
+                face(Point3::new(min.x, max.y, max.z), dx, -dz),
+                face(min, dx, dz),
+            ]),
+            bounding_box: Aabb::from_points(&[min, max]),
+        }
+    }
+
+    /// The six faces, which are what get sampled if the box is a light
+    pub fn faces(&self) -> &[Quad; 6] {
+        &self.faces
+    }
+}
@@ -0,0 +1,86 @@
+use crate::{
+    geometry::{Point3, Ray},
+    hittables::{
+        aabb::Aabb,
+        hittable::{HitRecord, Hittable},
+        local::{Frame, LocalHit, around_axis, cap_hit, quadratic_roots},
+    },
+    interval::Interval,
+    textures::material::Material,
+};
+
+use glam::Vec3;
+
+/// A cylinder between two points, optionally closed off with flat caps.
+/// Around the side u runs around the axis and v along it, and on the caps
+/// u runs around and v outwards
+#[derive(Clone, Debug)]
+pub struct Cylinder {
+    frame: Frame,
+    radius: f32,
+    height: f32,
+    capped: bool,
+    material: Material,
+}
+
+impl Hittable for Cylinder {
+    fn was_hit(&self, ray: Ray, interval: Interval) -> Option<HitRecord> {
+        let (origin, direction) = self.frame.ray_to_local(ray);
+        let side = quadratic_roots(
+            direction.truncate().length_squared(),
+            origin.truncate().dot(direction.truncate()),
+            origin.truncate().length_squared() - self.radius * self.radius,
+        )
+        .into_iter()
+        .flatten()
+        .map(|time| {
+            let point = origin + time * direction;
+            (0. ..=self.height).contains(&point.z).then(|| LocalHit {
+                time,
+                normal: point.with_z(0.) / self.radius,
+                u: around_axis(point),
+                v: point.z / self.height,
+            })
+        });
+        let caps = self
+            .capped
+            .then(|| {
+                [
+                    cap_hit(origin, direction, 0., self.radius, -1.),
+                    cap_hit(origin, direction, self.height, self.radius, 1.),
+                ]
+            })
+            .into_iter()
+            .flatten();
+        self.frame
+            .nearest(ray, interval, side.chain(caps), &self.material)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.frame.bounding_box(
+            Vec3::new(-self.radius, -self.radius, 0.),
+            Vec3::new(self.radius, self.radius, self.height),
+        )
+    }
+}
+
+impl Cylinder {
+    /// The cylinder with its axis from `base` to `top`
+    pub fn new(
+        base: impl Into<Point3>,
+        top: impl Into<Point3>,
+        radius: f32,
+        capped: bool,
+        material: impl Into<Material>,
+    ) -> Self {
+        let (base, top) = (base.into(), top.into());
+        let axis = *(top - base);
+        Self {
+            frame: Frame::new(base, axis),
+            radius,
+            height: axis.length(),
+            capped,
+            material: material.into(),
+        }
+    }
+}
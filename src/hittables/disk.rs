@@ -0,0 +1,151 @@
+use std::f32::consts::{PI, TAU};
+
+use crate::{
+    geometry::{Point3, Ray, VecRand},
+    hittables::{
+        aabb::Aabb,
+        hittable::{HitRecord, Hittable},
+        light::{Emitter, LightSample},
+    },
+    interval::Interval,
+    sampler::Sampler,
+    textures::{material::Material, texture::GetTexture},
+};
+
+use glam::{Vec2, Vec3};
+
+/// A flat circle, with u running around it and v outwards from the centre
+#[derive(Clone, Debug)]
+pub struct Disk {
+    center: Point3,
+    normal: Vec3,
+    tangent: Vec3,
+    bitangent: Vec3,
+    radius: f32,
+    material: Material,
+}
+
+impl Hittable for Disk {
+    fn was_hit(&self, ray: Ray, interval: Interval) -> Option<HitRecord> {
+        let (collision_time, offset) = self.intersection(ray)?;
+        if !interval.surrounds(collision_time) {
+            return None;
+        }
+        let (front_face, normal_vector) =
+            HitRecord::calc_front_face(ray, self.normal);
+        let (u, v) = self.get_uv(offset);
+        Some(HitRecord::new(
+            ray.at(collision_time),
+            normal_vector,
+            collision_time,
+            front_face,
+            self.material.clone(),
+            u,
+            v,
+        ))
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        // The disk reaches less far along an axis the closer its normal is
+        // to it
+        let extent = self.radius
+            * (Vec3::ONE - self.normal * self.normal)
+                .max(Vec3::ZERO)
+                .map(f32::sqrt);
+        Aabb::from_points(&[self.center - extent, self.center + extent])
+    }
+}
+
+impl Emitter for Disk {
+    fn is_emissive(&self) -> bool {
+        self.material.is_light
+    }
+
+    fn sample_from(
+        &self,
+        origin: Point3,
+        sampler: &mut Sampler,
+    ) -> Option<LightSample> {
+        // Uniform sampling over the disk's area
+        let on_disk = Vec3::random_on_unit_disk(sampler) * self.radius;
+        let offset = on_disk.x * self.tangent + on_disk.y * self.bitangent;
+        let point = self.center + offset;
+
+        let to_point = *(point - origin);
+        let distance = to_point.length();
+        let direction = to_point / distance;
+        let (u, v) = self.get_uv(Vec2::new(on_disk.x, on_disk.y));
+        Some(LightSample {
+            direction,
+            distance,
+            pdf: self.area_to_solid_angle(direction, distance)?,
+            emitted: self.material.texture.get_colour(u, v),
+        })
+    }
+
+    fn pdf_from(&self, origin: Point3, direction: Vec3) -> f32 {
+        self.intersection(Ray::new(origin, direction))
+            .filter(|&(distance, _)| distance > f32::EPSILON)
+            .and_then(|(distance, _)| {
+                self.area_to_solid_angle(direction, distance)
+            })
+            .unwrap_or(0.)
+    }
+}
+
+impl Disk {
+    pub fn new(
+        center: impl Into<Point3>,
+        normal: impl Into<Vec3>,
+        radius: f32,
+        material: impl Into<Material>,
+    ) -> Self {
+        let normal = normal.into().normalize();
+        let (tangent, bitangent) = normal.any_orthonormal_pair();
+        Self {
+            center: center.into(),
+            normal,
+            tangent,
+            bitangent,
+            radius,
+            material: material.into(),
+        }
+    }
+
+    /// The time `ray` crosses the disk's plane, and where it does relative
+    /// to the centre along the tangent and bitangent, if that's within the
+    /// disk
+    fn intersection(&self, ray: Ray) -> Option<(f32, Vec2)> {
+        let denominator = self.normal.dot(ray.direction);
+        if denominator.abs() < f32::EPSILON {
+            return None;
+        }
+        let collision_time =
+            self.normal.dot(*(self.center - ray.origin)) / denominator;
+        let in_plane = *(ray.at(collision_time) - self.center);
+        let offset =
+            Vec2::new(in_plane.dot(self.tangent), in_plane.dot(self.bitangent));
+        (offset.length_squared() <= self.radius * self.radius)
+            .then_some((collision_time, offset))
+    }
+
+    fn get_uv(&self, offset: Vec2) -> (f32, f32) {
+        (
+            0.5 + offset.y.atan2(offset.x) / TAU,
+            offset.length() / self.radius,
+        )
+    }
+
+    /// Converts the uniform area density into a solid angle density for a
+    /// point `distance` away along the unit vector `direction`
+    fn area_to_solid_angle(
+        &self,
+        direction: Vec3,
+        distance: f32,
+    ) -> Option<f32> {
+        let cosine = self.normal.dot(direction).abs();
+        let area = PI * self.radius * self.radius;
+        (cosine > f32::EPSILON && area > 0.)
+            .then(|| distance * distance / (cosine * area))
+    }
+}
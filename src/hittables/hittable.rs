@@ -3,7 +3,12 @@ use std::ops::Index;
 
 use crate::{
     geometry::{Point3, Ray},
-    hittables::{sphere::Sphere, triangle::Triangle},
+    hittables::{
+        aabb::Aabb, bvh::Bvh, capsule::Capsule, cone::Cone, cuboid::Cuboid,
+        cylinder::Cylinder, disk::Disk, instance::Instance, mesh::Mesh,
+        plane::Plane, quad::Quad, sphere::Sphere, torus::Torus,
+        triangle::Triangle,
+    },
     interval::Interval,
     textures::material::Material,
 };
@@ -19,6 +24,9 @@ pub struct HitRecord {
     pub material: Material,
     pub u: f32,
     pub v: f32,
+    /// Position of the hit object in the order it was added to the world,
+    /// once the world has filled it in
+    pub object: usize,
 }
 
 impl HitRecord {
@@ -39,6 +47,7 @@ impl HitRecord {
             material,
             u,
             v,
+            object: 0,
         }
     }
     pub fn calc_front_face(ray: Ray, outward_normal: Vec3) -> (bool, Vec3) {
@@ -57,6 +66,7 @@ impl HitRecord {
 #[enum_dispatch]
 pub trait Hittable {
     fn was_hit(&self, ray: Ray, interval: Interval) -> Option<HitRecord>;
+    fn bounding_box(&self) -> Aabb;
 }
 
 #[enum_dispatch(Hittable)]
@@ -64,21 +74,87 @@ pub trait Hittable {
 pub enum HittableObject {
     Sphere,
     Triangle,
+    Mesh,
+    Plane,
+    Quad,
+    Disk,
+    Cuboid,
+    Cylinder,
+    Cone,
+    Capsule,
+    Torus,
+    Instance,
 }
 
 #[derive(Debug, Default)]
 pub struct HittableList {
-    pub data: Vec<HittableObject>,
+    data: Vec<HittableObject>,
+    /// The position each object was added in, which `optimise` keeps with
+    /// it as it reorders them
+    ids: Vec<usize>,
+    bvh: Option<Bvh>,
+    /// Where the objects left out of the hierarchy for being unbounded
+    /// start, once `optimise` has moved them to the end
+    unbounded_from: usize,
 }
 
 impl FromIterator<HittableObject> for HittableList {
     fn from_iter<T: IntoIterator<Item = HittableObject>>(iter: T) -> Self {
+        let data = iter.into_iter().collect::<Vec<_>>();
         Self {
-            data: iter.into_iter().collect(),
+            ids: (0..data.len()).collect(),
+            data,
+            bvh: None,
+            unbounded_from: 0,
         }
     }
 }
 
+impl HittableList {
+    /// Adds an object, discarding any hierarchy built by `optimise`
+    pub fn push(&mut self, object: HittableObject) {
+        self.ids.push(self.data.len());
+        self.data.push(object);
+        self.bvh = None;
+    }
+
+    /// Builds a bounding volume hierarchy over the objects, reordering them
+    pub fn optimise(mut self) -> Self {
+        let mut objects = self
+            .ids
+            .drain(..)
+            .zip(self.data.drain(..))
+            .collect::<Vec<_>>();
+        // Planes and the like can't be split up by position, so they go
+        // after the hierarchy and are tested one by one
+        objects.sort_by_key(|(_, object)| !object.bounding_box().is_bounded());
+        self.unbounded_from = objects
+            .iter()
+            .position(|(_, object)| !object.bounding_box().is_bounded())
+            .unwrap_or(objects.len());
+        let mut unbounded = objects.split_off(self.unbounded_from);
+        self.bvh = Some(Bvh::build(&mut objects, |(_, object)| {
+            object.bounding_box()
+        }));
+        objects.append(&mut unbounded);
+        (self.ids, self.data) = objects.into_iter().unzip();
+        self
+    }
+
+    /// The objects, in the order the hierarchy keeps them
+    pub fn objects(&self) -> &[HittableObject] {
+        &self.data
+    }
+
+    /// The objects, in the order they were added
+    pub fn into_objects(self) -> Vec<HittableObject> {
+        let mut objects =
+            self.ids.into_iter().zip(self.data).collect::<Vec<_>>();
+        objects.sort_unstable_by_key(|&(id, _)| id);
+        objects.into_iter().map(|(_, object)| object).collect()
+    }
+}
+
 impl Index<usize> for HittableList {
     type Output = HittableObject;
 
@@ -89,16 +165,103 @@ impl Index<usize> for HittableList {
 
 impl Hittable for HittableList {
     fn was_hit(&self, ray: Ray, interval: Interval) -> Option<HitRecord> {
-        let mut closest = interval.max;
-        let mut out_data = None;
-        self.data.iter().for_each(|object| {
-            if let Some(data) =
-                object.was_hit(ray, Interval::new(interval.min, closest))
-            {
-                closest = data.collision_time;
-                out_data = Some(data);
-            }
-        });
+        let (mut out_data, linear_from) = match &self.bvh {
+            Some(bvh) => (
+                bvh.was_hit(ray, interval, |index, interval| {
+                    self.data[index].was_hit(ray, interval).map(|data| {
+                        HitRecord {
+                            object: self.ids[index],
+                            ..data
+                        }
+                    })
+                }),
+                self.unbounded_from,
+            ),
+            None => (None, 0),
+        };
+        let mut closest = out_data
+            .as_ref()
+            .map_or(interval.max, |data| data.collision_time);
+        self.data.iter().enumerate().skip(linear_from).for_each(
+            |(index, object)| {
+                if let Some(data) =
+                    object.was_hit(ray, Interval::new(interval.min, closest))
+                {
+                    closest = data.collision_time;
+                    out_data = Some(HitRecord {
+                        object: self.ids[index],
+                        ..data
+                    });
+                }
+            },
+        );
         out_data
     }
+    fn bounding_box(&self) -> Aabb {
+        let (bounds, linear_from) = match &self.bvh {
+            Some(bvh) => (bvh.bounding_box(), self.unbounded_from),
+            None => (Aabb::EMPTY, 0),
+        };
+        self.data[linear_from..]
+            .iter()
+            .fold(bounds, |bounds, object| bounds.union(object.bounding_box()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hits_report_scene_order_after_optimising() {
+        let material = Material::default();
+        let world = [
+            Plane::new(Point3::new(0., 0., -10.), Vec3::Z, material.clone())
+                .into(),
+            Sphere::new(Point3::new(0., 0., -5.), 1., material.clone()).into(),
+            Sphere::new(Point3::new(0., 0., -2.), 0.5, material.clone()).into(),
+            Sphere::new(Point3::new(3., 0., -2.), 0.5, material).into(),
+        ]
+        .into_iter()
+        .collect::<HittableList>()
+        .optimise();
+
+        let interval = Interval::new(0.001, f32::INFINITY);
+        let hit = |direction| {
+            world
+                .was_hit(Ray::new(Point3::new(0., 0., 0.), direction), interval)
+                .map(|hit| hit.object)
+        };
+        assert_eq!(hit(Vec3::NEG_Z), Some(2));
+        assert_eq!(hit(Vec3::new(1.5, 0., -1.)), Some(3));
+        assert_eq!(hit(Vec3::new(0., 4., -10.)), Some(0));
+    }
+
+    #[test]
+    fn into_objects_restores_scene_order() {
+        let material = Material::default();
+        let radii = [3., 1., 2., 0.5];
+        let world = radii
+            .iter()
+            .enumerate()
+            .map(|(index, &radius)| {
+                let center = Point3::new(10. * index as f32, 0., 0.);
+                Sphere::new(center, radius, material.clone()).into()
+            })
+            .chain([Plane::new(
+                Point3::new(0., -5., 0.),
+                Vec3::Y,
+                material.clone(),
+            )
+            .into()])
+            .collect::<HittableList>()
+            .optimise();
+
+        let objects = world.into_objects();
+        assert!(matches!(objects.last(), Some(HittableObject::Plane(_))));
+        for (object, index) in objects.iter().zip(0..radii.len()) {
+            let center = object.bounding_box().centroid();
+            assert_eq!(center.x, 10. * index as f32);
+        }
+    }
 }
@@ -0,0 +1,73 @@
+use std::sync::Arc;
+
+use crate::{
+    geometry::{Point3, Ray},
+    hittables::{
+        aabb::Aabb,
+        hittable::{HitRecord, Hittable, HittableList},
+    },
+    interval::Interval,
+};
+
+use glam::{Affine3A, Mat3A};
+
+/// A group of objects placed in the world by an affine transform. Groups are
+/// shared, so placing one many times costs little more memory than placing
+/// it once. Lights within an instance are only found by rays bouncing into
+/// them, rather than being sampled directly
+#[derive(Clone, Debug)]
+pub struct Instance {
+    group: Arc<HittableList>,
+    to_world: Affine3A,
+    to_object: Affine3A,
+    /// The inverse transpose of the linear part, which keeps normals at
+    /// right angles to surfaces that have been stretched
+    normal_to_world: Mat3A,
+    bounding_box: Aabb,
+}
+
+impl Hittable for Instance {
+    fn was_hit(&self, ray: Ray, interval: Interval) -> Option<HitRecord> {
+        // The direction isn't normalised, so times along the ray are the
+        // same in both spaces
+        let object_ray = Ray::new(
+            self.to_object.transform_point3(*ray.origin).into(),
+            self.to_object.transform_vector3(ray.direction),
+        );
+        let hit = self.group.was_hit(object_ray, interval)?;
+        Some(HitRecord {
+            collision_point: self
+                .to_world
+                .transform_point3(*hit.collision_point)
+                .into(),
+            normal_vector: (self.normal_to_world * hit.normal_vector)
+                .normalize(),
+            ..hit
+        })
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bounding_box
+    }
+}
+
+impl Instance {
+    /// Places `group`, which should already be optimised, by `transform`
+    pub fn new(group: Arc<HittableList>, transform: Affine3A) -> Self {
+        let bounds = group.bounding_box();
+        let bounding_box = if bounds.is_bounded() {
+            Aabb::from_points(&bounds.corners().map(|corner| {
+                Point3::from(transform.transform_point3(*corner))
+            }))
+        } else {
+            Aabb::UNIVERSE
+        };
+        Self {
+            group,
+            to_world: transform,
+            to_object: transform.inverse(),
+            normal_to_world: transform.matrix3.inverse().transpose(),
+            bounding_box,
+        }
+    }
+}
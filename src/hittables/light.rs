@@ -0,0 +1,133 @@
+use enum_dispatch::enum_dispatch;
+
+use crate::{
+    colour::Colour,
+    geometry::Point3,
+    hittables::{
+        disk::Disk, hittable::HittableObject, quad::Quad, sphere::Sphere,
+        triangle::Triangle,
+    },
+    sampler::{Sample, Sampler},
+};
+
+use glam::Vec3;
+
+/// A point on a light chosen for direct lighting, as seen from the shading
+/// point
+#[derive(Copy, Clone, Debug)]
+pub struct LightSample {
+    pub direction: Vec3,
+    pub distance: f32,
+    pub pdf: f32,
+    pub emitted: Colour,
+}
+
+#[enum_dispatch]
+pub trait Emitter {
+    fn is_emissive(&self) -> bool;
+    /// Samples a direction from `origin` towards the object
+    fn sample_from(
+        &self,
+        origin: Point3,
+        sampler: &mut Sampler,
+    ) -> Option<LightSample>;
+    /// Solid angle density with which `sample_from` picks `direction`
+    fn pdf_from(&self, origin: Point3, direction: Vec3) -> f32;
+}
+
+#[enum_dispatch(Emitter)]
+#[derive(Clone, Debug)]
+pub enum Light {
+    Sphere,
+    Triangle,
+    Quad,
+    Disk,
+}
+
+#[derive(Debug, Default)]
+pub struct LightList {
+    lights: Vec<Light>,
+}
+
+impl LightList {
+    /// Gathers every emissive sphere, triangle, quad and disk, taking boxes
+    /// a face at a time
+    pub fn from_objects(objects: &[HittableObject]) -> Self {
+        let lights = objects
+            .iter()
+            .flat_map(|object| -> Vec<Light> {
+                match object {
+                    HittableObject::Sphere(sphere) => {
+                        vec![sphere.clone().into()]
+                    }
+                    HittableObject::Triangle(triangle) => {
+                        vec![triangle.clone().into()]
+                    }
+                    HittableObject::Quad(quad) => vec![quad.clone().into()],
+                    HittableObject::Disk(disk) => vec![disk.clone().into()],
+                    HittableObject::Cuboid(cuboid) => cuboid
+                        .faces()
+                        .iter()
+                        .map(|face| face.clone().into())
+                        .collect(),
+                    HittableObject::Mesh(_)
+                    | HittableObject::Plane(_)
+                    | HittableObject::Cylinder(_)
+                    | HittableObject::Cone(_)
+                    | HittableObject::Capsule(_)
+                    | HittableObject::Torus(_)
+                    | HittableObject::Instance(_) => Vec::new(),
+                }
+            })
+            .filter(|light: &Light| light.is_emissive())
+            .collect();
+        Self { lights }
+    }
+
+    pub fn len(&self) -> usize {
+        self.lights.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lights.is_empty()
+    }
+
+    /// Samples a single light, chosen uniformly
+    pub fn sample_from(
+        &self,
+        origin: Point3,
+        sampler: &mut Sampler,
+    ) -> Option<LightSample> {
+        if self.lights.is_empty() {
+            return None;
+        }
+        let count = self.lights.len();
+        let index = ((sampler.get_1d() * count as f32) as usize).min(count - 1);
+        let sample = self.lights[index].sample_from(origin, sampler)?;
+        Some(LightSample {
+            pdf: self.pdf_from(origin, sample.direction),
+            ..sample
+        })
+    }
+
+    /// Density of `sample_from` over all lights, since they can overlap
+    pub fn pdf_from(&self, origin: Point3, direction: Vec3) -> f32 {
+        let total = self
+            .lights
+            .iter()
+            .map(|light| light.pdf_from(origin, direction))
+            .sum::<f32>();
+        total / self.lights.len() as f32
+    }
+}
+
+/// Veach's power heuristic weight for a sample from the strategy with density
+/// `pdf` against one with density `other_pdf`
+pub fn power_heuristic(pdf: f32, other_pdf: f32) -> f32 {
+    let (pdf, other_pdf) = (pdf * pdf, other_pdf * other_pdf);
+    if pdf + other_pdf > 0. {
+        pdf / (pdf + other_pdf)
+    } else {
+        0.
+    }
+}
@@ -0,0 +1,295 @@
+//! Shared pieces for objects built around an axis, which are intersected in
+//! their own frame where the axis is z
+
+use std::f32::consts::TAU;
+
+use crate::{
+    geometry::{Point3, Ray},
+    hittables::{aabb::Aabb, hittable::HitRecord},
+    interval::Interval,
+    textures::material::Material,
+};
+
+use glam::Vec3;
+
+/// An orthonormal frame with its z axis along an object's axis
+#[derive(Copy, Clone, Debug)]
+pub(super) struct Frame {
+    origin: Point3,
+    tangent: Vec3,
+    bitangent: Vec3,
+    axis: Vec3,
+}
+
+/// A hit in an object's own frame, before it's turned back into the world's
+#[derive(Copy, Clone, Debug)]
+pub(super) struct LocalHit {
+    pub time: f32,
+    pub normal: Vec3,
+    pub u: f32,
+    pub v: f32,
+}
+
+impl Frame {
+    /// The frame at `origin` with z along `axis`, which needn't be unit
+    /// length
+    pub fn new(origin: Point3, axis: Vec3) -> Self {
+        let axis = axis.normalize();
+        let (tangent, bitangent) = axis.any_orthonormal_pair();
+        Self {
+            origin,
+            tangent,
+            bitangent,
+            axis,
+        }
+    }
+
+    fn vector_to_local(&self, vector: Vec3) -> Vec3 {
+        Vec3::new(
+            vector.dot(self.tangent),
+            vector.dot(self.bitangent),
+            vector.dot(self.axis),
+        )
+    }
+
+    fn vector_to_world(&self, vector: Vec3) -> Vec3 {
+        vector.x * self.tangent
+            + vector.y * self.bitangent
+            + vector.z * self.axis
+    }
+
+    /// `ray`'s origin and direction in the frame. The frame is rigid, so
+    /// times along the ray are the same in both
+    pub fn ray_to_local(&self, ray: Ray) -> (Vec3, Vec3) {
+        (
+            self.vector_to_local(*(ray.origin - self.origin)),
+            self.vector_to_local(ray.direction),
+        )
+    }
+
+    /// Box around the frame's box from `min` to `max`
+    pub fn bounding_box(&self, min: Vec3, max: Vec3) -> Aabb {
+        let local = Aabb::from_points(&[min.into(), max.into()]);
+        Aabb::from_points(
+            &local
+                .corners()
+                .map(|corner| self.origin + self.vector_to_world(*corner)),
+        )
+    }
+
+    /// The closest of `hits` within `interval`, turned into a world hit
+    pub fn nearest(
+        &self,
+        ray: Ray,
+        interval: Interval,
+        hits: impl IntoIterator<Item = Option<LocalHit>>,
+        material: &Material,
+    ) -> Option<HitRecord> {
+        let hit = hits
+            .into_iter()
+            .flatten()
+            .filter(|hit| interval.surrounds(hit.time))
+            .min_by(|a, b| a.time.total_cmp(&b.time))?;
+        let outward_normal = self.vector_to_world(hit.normal);
+        let (front_face, normal_vector) =
+            HitRecord::calc_front_face(ray, outward_normal);
+        Some(HitRecord::new(
+            ray.at(hit.time),
+            normal_vector,
+            hit.time,
+            front_face,
+            material.clone(),
+            hit.u,
+            hit.v,
+        ))
+    }
+}
+
+/// Where a ray from `origin` along `direction` crosses the plane at height
+/// `height`, if it's within `radius` of the axis, as a hit on a cap facing
+/// `facing` along z. u runs around the cap and v outwards
+pub(super) fn cap_hit(
+    origin: Vec3,
+    direction: Vec3,
+    height: f32,
+    radius: f32,
+    facing: f32,
+) -> Option<LocalHit> {
+    if direction.z.abs() < f32::EPSILON {
+        return None;
+    }
+    let time = (height - origin.z) / direction.z;
+    let point = origin + time * direction;
+    let distance = point.truncate().length();
+    (distance <= radius).then(|| LocalHit {
+        time,
+        normal: Vec3::new(0., 0., facing),
+        u: around_axis(point),
+        v: distance / radius,
+    })
+}
+
+/// How far around the z axis `point` is, from zero to one
+pub(super) fn around_axis(point: Vec3) -> f32 {
+    0.5 + point.y.atan2(point.x) / TAU
+}
+
+/// Real roots of `a t² + 2 half_b t + c`, smallest first
+pub(super) fn quadratic_roots(a: f32, half_b: f32, c: f32) -> Option<[f32; 2]> {
+    if a.abs() < f32::EPSILON {
+        return None;
+    }
+    let discriminant = half_b * half_b - a * c;
+    if discriminant < 0. {
+        return None;
+    }
+    let root = discriminant.sqrt();
+    let (first, second) = ((-half_b - root) / a, (-half_b + root) / a);
+    Some(if first < second {
+        [first, second]
+    } else {
+        [second, first]
+    })
+}
+
+/// Real roots of `t⁴ + a t³ + b t² + c t + d`, by Ferrari's method in double
+/// precision and then polished with a couple of Newton steps, since the
+/// closed form loses a lot of accuracy
+pub(super) fn quartic_roots(a: f64, b: f64, c: f64, d: f64) -> Vec<f64> {
+    // Substituting t = y - a / 4 leaves y⁴ + p y² + q y + r
+    let shift = a / 4.;
+    let p = b - 6. * shift * shift;
+    let q = c - 2. * b * shift + 8. * shift * shift * shift;
+    let r = d - c * shift + b * shift * shift - 3. * shift.powi(4);
+
+    let mut roots = Vec::with_capacity(4);
+    if q.abs() < 1e-12 {
+        // Quadratic in y²
+        let discriminant = p * p - 4. * r;
+        if discriminant >= 0. {
+            for square in [
+                (-p - discriminant.sqrt()) / 2.,
+                (-p + discriminant.sqrt()) / 2.,
+            ] {
+                if square >= 0. {
+                    roots.extend([square.sqrt(), -square.sqrt()]);
+                }
+            }
+        }
+    } else {
+        // Adding m to both sides' y² + p / 2 makes the right a perfect
+        // square for the positive root m of the resolvent cubic, leaving
+        // two quadratics
+        let m = largest_cubic_root(p, p * p / 4. - r, -q * q / 8.).max(1e-12);
+        let s = (2. * m).sqrt();
+        for (sign, offset) in [(-1., q / (2. * s)), (1., -q / (2. * s))] {
+            let (half_b, constant) = (sign * s / 2., p / 2. + m + offset);
+            let discriminant = half_b * half_b - constant;
+            if discriminant >= 0. {
+                roots.extend([
+                    -half_b - discriminant.sqrt(),
+                    -half_b + discriminant.sqrt(),
+                ]);
+            }
+        }
+    }
+
+    roots
+        .into_iter()
+        .map(|root| {
+            let mut t = root - shift;
+            for _ in 0..2 {
+                let value = (((t + a) * t + b) * t + c) * t + d;
+                let slope = ((4. * t + 3. * a) * t + 2. * b) * t + c;
+                if slope.abs() > f64::EPSILON {
+                    t -= value / slope;
+                }
+            }
+            t
+        })
+        .collect()
+}
+
+/// Largest real root of `m³ + a m² + b m + c`
+fn largest_cubic_root(a: f64, b: f64, c: f64) -> f64 {
+    // Substituting m = x - a / 3 leaves x³ + p x + q
+    let shift = a / 3.;
+    let p = b - a * shift;
+    let q = 2. * shift * shift * shift - b * shift + c;
+    let discriminant = q * q / 4. + p * p * p / 27.;
+    let x = if discriminant >= 0. {
+        let root = discriminant.sqrt();
+        (-q / 2. + root).cbrt() + (-q / 2. - root).cbrt()
+    } else {
+        let radius = (-p / 3.).sqrt();
+        let angle =
+            (-q / (2. * radius * radius * radius)).clamp(-1., 1.).acos();
+        2. * radius * (angle / 3.).cos()
+    };
+    x - shift
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(mut roots: Vec<f64>) -> Vec<f64> {
+        roots.sort_by(f64::total_cmp);
+        roots
+    }
+
+    fn assert_close(actual: &[f64], expected: &[f64]) {
+        assert_eq!(actual.len(), expected.len(), "{actual:?}");
+        for (actual, expected) in actual.iter().zip(expected) {
+            assert!((actual - expected).abs() < 1e-9, "{actual} != {expected}");
+        }
+    }
+
+    #[test]
+    fn quartic_with_four_roots() {
+        // (t - 1)(t - 2)(t - 3)(t - 4)
+        let roots = sorted(quartic_roots(-10., 35., -50., 24.));
+        assert_close(&roots, &[1., 2., 3., 4.]);
+    }
+
+    #[test]
+    fn quartic_with_two_roots() {
+        // (t² + 1)(t - 2)(t + 3)
+        let roots = sorted(quartic_roots(1., -5., 1., -6.));
+        assert_close(&roots, &[-3., 2.]);
+    }
+
+    #[test]
+    fn quartic_in_t_squared() {
+        // (t² - 1)(t² - 4), which has no odd terms once shifted
+        let roots = sorted(quartic_roots(0., -5., 0., 4.));
+        assert_close(&roots, &[-2., -1., 1., 2.]);
+    }
+
+    #[test]
+    fn quartic_without_real_roots() {
+        // (t² + 1)(t² + 4)
+        assert!(quartic_roots(0., 5., 0., 4.).is_empty());
+    }
+
+    #[test]
+    fn largest_cubic_root_of_three() {
+        // (m - 1)(m - 2)(m - 3)
+        assert_close(&[largest_cubic_root(-6., 11., -6.)], &[3.]);
+    }
+
+    #[test]
+    fn largest_cubic_root_of_one() {
+        assert_close(&[largest_cubic_root(0., 0., -8.)], &[2.]);
+        // (m + 1)(m² + 1)
+        assert_close(&[largest_cubic_root(1., 1., 1.)], &[-1.]);
+    }
+
+    #[test]
+    fn quadratic_roots_smallest_first() {
+        // -(t - 1)(t - 3), with a negative leading term
+        assert_eq!(quadratic_roots(-1., 2., -3.), Some([1., 3.]));
+        assert_eq!(quadratic_roots(1., 0., 1.), None);
+        assert_eq!(quadratic_roots(0., 1., 1.), None);
+    }
+}
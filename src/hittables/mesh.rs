@@ -0,0 +1,130 @@
+use std::sync::Arc;
+
+use crate::{
+    geometry::{Point3, Ray},
+    hittables::{
+        aabb::Aabb,
+        bvh::Bvh,
+        hittable::{HitRecord, Hittable},
+        triangle::{Triangle, interpolate},
+    },
+    interval::Interval,
+    textures::material::Material,
+};
+
+use glam::{Vec2, Vec3};
+
+/// Indices of a single triangle into the buffers of a `Mesh`
+#[derive(Copy, Clone, Debug)]
+pub struct MeshFace {
+    pub positions: [u32; 3],
+    pub normals: Option<[u32; 3]>,
+    pub uvs: Option<[u32; 3]>,
+    pub material: u32,
+}
+
+#[derive(Debug)]
+struct MeshData {
+    positions: Vec<Point3>,
+    normals: Vec<Vec3>,
+    uvs: Vec<Vec2>,
+    faces: Vec<MeshFace>,
+    materials: Vec<Material>,
+    bvh: Bvh,
+}
+
+/// Triangle mesh sharing one set of vertex buffers between all its faces
+#[derive(Clone, Debug)]
+pub struct Mesh {
+    data: Arc<MeshData>,
+}
+
+impl Hittable for Mesh {
+    fn was_hit(&self, ray: Ray, interval: Interval) -> Option<HitRecord> {
+        self.data.bvh.was_hit(ray, interval, |index, interval| {
+            self.face_was_hit(&self.data.faces[index], ray, interval)
+        })
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.data.bvh.bounding_box()
+    }
+}
+
+impl Mesh {
+    pub fn new(
+        positions: Vec<Point3>,
+        normals: Vec<Vec3>,
+        uvs: Vec<Vec2>,
+        mut faces: Vec<MeshFace>,
+        materials: Vec<Material>,
+    ) -> Self {
+        let bvh = Bvh::build(&mut faces, |face| {
+            Aabb::from_points(
+                &face.positions.map(|index| positions[index as usize]),
+            )
+        });
+        Self {
+            data: Arc::new(MeshData {
+                positions,
+                normals,
+                uvs,
+                faces,
+                materials,
+                bvh,
+            }),
+        }
+    }
+
+    pub fn face_count(&self) -> usize {
+        self.data.faces.len()
+    }
+
+    pub fn material_count(&self) -> usize {
+        self.data.materials.len()
+    }
+
+    fn face_was_hit(
+        &self,
+        face: &MeshFace,
+        ray: Ray,
+        interval: Interval,
+    ) -> Option<HitRecord> {
+        let data = &self.data;
+        let corners =
+            face.positions.map(|index| data.positions[index as usize]);
+        let (collision_time, u, v) = Triangle::moller_trumbore_intersection(
+            corners,
+            ray.origin,
+            ray.direction,
+        )?;
+
+        if !interval.surrounds(collision_time) {
+            return None;
+        }
+
+        let collision_point = ray.at(collision_time);
+        let face_normal = (corners[1] - corners[0])
+            .cross(*(corners[2] - corners[0]))
+            .normalize();
+        let front_face = face_normal.dot(ray.direction.normalize()) > 0.;
+
+        let normal = face.normals.map_or(face_normal, |indices| {
+            interpolate(indices.map(|index| data.normals[index as usize]), u, v)
+        });
+        let (u, v) = face.uvs.map_or((u, v), |indices| {
+            interpolate(indices.map(|index| data.uvs[index as usize]), u, v)
+                .into()
+        });
+
+        Some(HitRecord::new(
+            collision_point,
+            normal,
+            collision_time,
+            front_face,
+            data.materials[face.material as usize].clone(),
+            u,
+            v,
+        ))
+    }
+}
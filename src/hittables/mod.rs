@@ -1,3 +1,17 @@
-pub mod hittable;
-pub mod sphere;
-pub mod triangle;
+pub(crate) mod aabb;
+pub(crate) mod bvh;
+pub(crate) mod capsule;
+pub(crate) mod cone;
+pub(crate) mod cuboid;
+pub(crate) mod cylinder;
+pub(crate) mod disk;
+pub(crate) mod hittable;
+pub(crate) mod instance;
+pub(crate) mod light;
+mod local;
+pub(crate) mod mesh;
+pub(crate) mod plane;
+pub(crate) mod quad;
+pub(crate) mod sphere;
+pub(crate) mod torus;
+pub(crate) mod triangle;
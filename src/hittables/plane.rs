@@ -0,0 +1,73 @@
+use crate::{
+    geometry::{Point3, Ray},
+    hittables::{
+        aabb::Aabb,
+        hittable::{HitRecord, Hittable},
+    },
+    interval::Interval,
+    textures::material::Material,
+};
+
+use glam::Vec3;
+
+/// An infinite plane, with texture coordinates measured in world units
+/// along two directions within it
+#[derive(Clone, Debug)]
+pub struct Plane {
+    point: Point3,
+    normal: Vec3,
+    tangent: Vec3,
+    bitangent: Vec3,
+    material: Material,
+}
+
+impl Hittable for Plane {
+    fn was_hit(&self, ray: Ray, interval: Interval) -> Option<HitRecord> {
+        let denominator = self.normal.dot(ray.direction);
+        if denominator.abs() < f32::EPSILON {
+            return None;
+        }
+        let collision_time =
+            self.normal.dot(*(self.point - ray.origin)) / denominator;
+        if !interval.surrounds(collision_time) {
+            return None;
+        }
+
+        let collision_point = ray.at(collision_time);
+        let (front_face, normal_vector) =
+            HitRecord::calc_front_face(ray, self.normal);
+        let offset = *(collision_point - self.point);
+        Some(HitRecord::new(
+            collision_point,
+            normal_vector,
+            collision_time,
+            front_face,
+            self.material.clone(),
+            offset.dot(self.tangent),
+            offset.dot(self.bitangent),
+        ))
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        Aabb::UNIVERSE
+    }
+}
+
+impl Plane {
+    /// The plane through `point` facing `normal`
+    pub fn new(
+        point: impl Into<Point3>,
+        normal: impl Into<Vec3>,
+        material: impl Into<Material>,
+    ) -> Self {
+        let normal = normal.into().normalize();
+        let (tangent, bitangent) = normal.any_orthonormal_pair();
+        Self {
+            point: point.into(),
+            normal,
+            tangent,
+            bitangent,
+            material: material.into(),
+        }
+    }
+}
@@ -0,0 +1,143 @@
+use crate::{
+    geometry::{Point3, Ray},
+    hittables::{
+        aabb::Aabb,
+        hittable::{HitRecord, Hittable},
+        light::{Emitter, LightSample},
+    },
+    interval::Interval,
+    sampler::{Sample, Sampler},
+    textures::{material::Material, texture::GetTexture},
+};
+
+use glam::{Vec2, Vec3};
+
+/// A parallelogram spanned by two edges from a corner, with u and v running
+/// from zero to one along the edges
+#[derive(Clone, Debug)]
+pub struct Quad {
+    corner: Point3,
+    edge_u: Vec3,
+    edge_v: Vec3,
+    material: Material,
+    normal: Vec3,
+    /// The edges' cross product over its squared length, which turns a
+    /// point in the plane into its coordinates along the edges
+    to_edges: Vec3,
+    area: f32,
+}
+
+impl Hittable for Quad {
+    fn was_hit(&self, ray: Ray, interval: Interval) -> Option<HitRecord> {
+        let (collision_time, uv) = self.intersection(ray)?;
+        if !interval.surrounds(collision_time) {
+            return None;
+        }
+        let (front_face, normal_vector) =
+            HitRecord::calc_front_face(ray, self.normal);
+        Some(HitRecord::new(
+            ray.at(collision_time),
+            normal_vector,
+            collision_time,
+            front_face,
+            self.material.clone(),
+            uv.x,
+            uv.y,
+        ))
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        Aabb::from_points(&[
+            self.corner,
+            self.corner + self.edge_u,
+            self.corner + self.edge_v,
+            self.corner + self.edge_u + self.edge_v,
+        ])
+    }
+}
+
+impl Emitter for Quad {
+    fn is_emissive(&self) -> bool {
+        self.material.is_light
+    }
+
+    fn sample_from(
+        &self,
+        origin: Point3,
+        sampler: &mut Sampler,
+    ) -> Option<LightSample> {
+        // Uniform sampling over the quad's area
+        let uv = sampler.get_2d();
+        let point = self.corner + uv.x * self.edge_u + uv.y * self.edge_v;
+
+        let to_point = *(point - origin);
+        let distance = to_point.length();
+        let direction = to_point / distance;
+        Some(LightSample {
+            direction,
+            distance,
+            pdf: self.area_to_solid_angle(direction, distance)?,
+            emitted: self.material.texture.get_colour(uv.x, uv.y),
+        })
+    }
+
+    fn pdf_from(&self, origin: Point3, direction: Vec3) -> f32 {
+        self.intersection(Ray::new(origin, direction))
+            .filter(|&(distance, _)| distance > f32::EPSILON)
+            .and_then(|(distance, _)| {
+                self.area_to_solid_angle(direction, distance)
+            })
+            .unwrap_or(0.)
+    }
+}
+
+impl Quad {
+    pub fn new(
+        corner: impl Into<Point3>,
+        edge_u: impl Into<Vec3>,
+        edge_v: impl Into<Vec3>,
+        material: impl Into<Material>,
+    ) -> Self {
+        let (edge_u, edge_v) = (edge_u.into(), edge_v.into());
+        let cross = edge_u.cross(edge_v);
+        Self {
+            corner: corner.into(),
+            edge_u,
+            edge_v,
+            material: material.into(),
+            normal: cross.normalize(),
+            to_edges: cross / cross.length_squared(),
+            area: cross.length(),
+        }
+    }
+
+    /// The time `ray` crosses the quad's plane, and where along the edges
+    /// it does, if that's within the quad
+    fn intersection(&self, ray: Ray) -> Option<(f32, Vec2)> {
+        let denominator = self.normal.dot(ray.direction);
+        if denominator.abs() < f32::EPSILON {
+            return None;
+        }
+        let collision_time =
+            self.normal.dot(*(self.corner - ray.origin)) / denominator;
+        let in_plane = *(ray.at(collision_time) - self.corner);
+        let uv = Vec2::new(
+            self.to_edges.dot(in_plane.cross(self.edge_v)),
+            self.to_edges.dot(self.edge_u.cross(in_plane)),
+        );
+        (uv.cmpge(Vec2::ZERO).all() && uv.cmple(Vec2::ONE).all())
+            .then_some((collision_time, uv))
+    }
+
+    /// Converts the uniform area density into a solid angle density for a
+    /// point `distance` away along the unit vector `direction`
+    fn area_to_solid_angle(
+        &self,
+        direction: Vec3,
+        distance: f32,
+    ) -> Option<f32> {
+        let cosine = self.normal.dot(direction).abs();
+        (cosine > f32::EPSILON && self.area > 0.)
+            .then(|| distance * distance / (cosine * self.area))
+    }
+}
@@ -1,15 +1,20 @@
 use std::f32::consts::{PI, TAU};
 
-use derive_more::Constructor;
-
 use crate::{
     geometry::{Point3, Ray},
-    hittables::hittable::{HitRecord, Hittable},
+    hittables::{
+        aabb::Aabb,
+        hittable::{HitRecord, Hittable},
+        light::{Emitter, LightSample},
+    },
     interval::Interval,
-    textures::material::Material,
+    sampler::{Sample, Sampler},
+    textures::{material::Material, texture::GetTexture},
 };
 
-#[derive(Clone, Debug, Constructor)]
+use glam::Vec3;
+
+#[derive(Clone, Debug)]
 pub struct Sphere {
     pub center: Point3,
     pub radius: f32,
@@ -37,9 +42,90 @@ impl Hittable for Sphere {
             v,
         ))
     }
+
+    fn bounding_box(&self) -> Aabb {
+        Aabb::from_points(&[
+            self.center - Point3::new(self.radius, self.radius, self.radius),
+            self.center + self.radius,
+        ])
+    }
+}
+
+impl Emitter for Sphere {
+    fn is_emissive(&self) -> bool {
+        self.material.is_light
+    }
+
+    fn sample_from(
+        &self,
+        origin: Point3,
+        sampler: &mut Sampler,
+    ) -> Option<LightSample> {
+        // Sample uniformly within the cone the sphere subtends
+        let cos_theta_max = self.cos_theta_max(origin)?;
+        let sample = sampler.get_2d();
+        let cos_theta = 1. - sample.x * (1. - cos_theta_max);
+        let sin_theta = (1. - cos_theta * cos_theta).sqrt();
+        let phi = TAU * sample.y;
+
+        let axis = (self.center - origin).normalize();
+        let (tangent, bitangent) = axis.any_orthonormal_pair();
+        let direction = (phi.cos() * sin_theta) * tangent
+            + (phi.sin() * sin_theta) * bitangent
+            + cos_theta * axis;
+
+        let ray = Ray::new(origin, direction);
+        let (first, second) =
+            self.ray_intersections(ray, Interval::new(0., f32::INFINITY));
+        let distance = first.or(second)?;
+        let (u, v) = self.get_uv(ray.at(distance));
+        Some(LightSample {
+            direction,
+            distance,
+            pdf: 1. / (TAU * (1. - cos_theta_max)),
+            emitted: self.material.texture.get_colour(u, v),
+        })
+    }
+
+    fn pdf_from(&self, origin: Point3, direction: Vec3) -> f32 {
+        let Some(cos_theta_max) = self.cos_theta_max(origin) else {
+            return 0.;
+        };
+        let ray = Ray::new(origin, direction);
+        match self.ray_intersections(ray, Interval::new(0., f32::INFINITY)) {
+            (None, None) => 0.,
+            _ => 1. / (TAU * (1. - cos_theta_max)),
+        }
+    }
 }
 
 impl Sphere {
+    pub fn new(
+        center: impl Into<Point3>,
+        radius: f32,
+        material: impl Into<Material>,
+    ) -> Self {
+        Self {
+            center: center.into(),
+            radius,
+            material: material.into(),
+        }
+    }
+
+    pub fn with_material(mut self, material: impl Into<Material>) -> Self {
+        self.material = material.into();
+        self
+    }
+
+    /// Cosine of the half-angle of the cone the sphere subtends from `origin`,
+    /// which is undefined inside the sphere
+    fn cos_theta_max(&self, origin: Point3) -> Option<f32> {
+        let distance_squared = (self.center - origin).length_squared();
+        let radius_squared = self.radius * self.radius;
+        (distance_squared > radius_squared)
+            .then(|| (1. - radius_squared / distance_squared).sqrt())
+    }
+
     pub fn get_uv(&self, point: Point3) -> (f32, f32) {
         let vector = (self.center - point).normalize();
 
@@ -0,0 +1,154 @@
+use std::f32::consts::TAU;
+
+use crate::{
+    geometry::{Point3, Ray},
+    hittables::{
+        aabb::Aabb,
+        hittable::{HitRecord, Hittable},
+        local::{Frame, LocalHit, around_axis, quartic_roots},
+    },
+    interval::Interval,
+    textures::material::Material,
+};
+
+use glam::{DVec3, Vec3};
+
+/// A ring with a circular cross section, lying flat around an axis. u runs
+/// around the ring and v around the tube
+#[derive(Clone, Debug)]
+pub struct Torus {
+    frame: Frame,
+    /// Distance from the centre to the middle of the tube
+    major_radius: f32,
+    /// Radius of the tube
+    minor_radius: f32,
+    material: Material,
+}
+
+impl Hittable for Torus {
+    fn was_hit(&self, ray: Ray, interval: Interval) -> Option<HitRecord> {
+        let (origin, direction) = self.frame.ray_to_local(ray);
+        // The quartic is simpler along a unit direction, so times are scaled
+        // back afterwards
+        let speed = direction.length();
+        let (o, d) = (origin.as_dvec3(), (direction / speed).as_dvec3());
+        let major_squared = f64::from(self.major_radius).powi(2);
+        let minor_squared = f64::from(self.minor_radius).powi(2);
+        // Points on the torus satisfy
+        // (|p|² - R² - r²)² = 4R² (r² - z²)
+        let along = o.dot(d);
+        let excess = o.length_squared() - major_squared - minor_squared;
+        let roots = quartic_roots(
+            4. * along,
+            2. * excess + 4. * along * along + 4. * major_squared * d.z * d.z,
+            4. * along * excess + 8. * major_squared * o.z * d.z,
+            excess * excess - 4. * major_squared * (minor_squared - o.z * o.z),
+        );
+        let hits = roots.into_iter().map(|root| {
+            self.local_hit((root / f64::from(speed)) as f32, o + root * d)
+        });
+        self.frame.nearest(ray, interval, hits, &self.material)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let outer = self.major_radius + self.minor_radius;
+        self.frame.bounding_box(
+            Vec3::new(-outer, -outer, -self.minor_radius),
+            Vec3::new(outer, outer, self.minor_radius),
+        )
+    }
+}
+
+impl Torus {
+    /// The torus around `centre`, lying in the plane facing `axis`
+    pub fn new(
+        centre: impl Into<Point3>,
+        axis: impl Into<Vec3>,
+        major_radius: f32,
+        minor_radius: f32,
+        material: impl Into<Material>,
+    ) -> Self {
+        Self {
+            frame: Frame::new(centre.into(), axis.into()),
+            major_radius,
+            minor_radius,
+            material: material.into(),
+        }
+    }
+
+    /// A hit `time` along the ray, at `point` in the torus's frame
+    fn local_hit(&self, time: f32, point: DVec3) -> Option<LocalHit> {
+        if !time.is_finite() {
+            return None;
+        }
+        // Outwards from the middle of the tube, which is the nearest point
+        // on the ring through it
+        let major_radius = f64::from(self.major_radius);
+        let from_axis = point.truncate().length();
+        let ring = point.truncate().normalize_or_zero() * major_radius;
+        let outwards = point - ring.extend(0.);
+        Some(LocalHit {
+            time,
+            normal: outwards.as_vec3().normalize(),
+            u: around_axis(point.as_vec3()),
+            v: 0.5 + (point.z.atan2(from_axis - major_radius) as f32) / TAU,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn torus() -> Torus {
+        Torus::new(
+            Point3::new(0., 0., 0.),
+            Vec3::Y,
+            2.,
+            0.5,
+            Material::default(),
+        )
+    }
+
+    fn hit(origin: Point3, direction: Vec3) -> Option<HitRecord> {
+        torus().was_hit(
+            Ray::new(origin, direction),
+            Interval::new(0.001, f32::INFINITY),
+        )
+    }
+
+    fn assert_hit(hit: Option<HitRecord>, time: f32, normal: Vec3) {
+        let hit = hit.expect("the ray should hit the torus");
+        assert!((hit.collision_time - time).abs() < 1e-4, "{hit:?}");
+        assert!(hit.normal_vector.abs_diff_eq(normal, 1e-4), "{hit:?}");
+    }
+
+    #[test]
+    fn hits_the_outside_of_the_ring() {
+        let hit = hit(Point3::new(5., 0., 0.), Vec3::NEG_X);
+        assert_hit(hit, 2.5, Vec3::X);
+    }
+
+    #[test]
+    fn hits_the_top_of_the_tube() {
+        let hit = hit(Point3::new(0., 5., -2.), Vec3::NEG_Y);
+        assert_hit(hit, 4.5, Vec3::Y);
+    }
+
+    #[test]
+    fn hits_the_inside_of_the_ring_from_the_hole() {
+        let hit = hit(Point3::new(0., 0., 0.), Vec3::Z);
+        assert_hit(hit, 1.5, Vec3::NEG_Z);
+    }
+
+    #[test]
+    fn times_follow_the_direction_length() {
+        let hit = hit(Point3::new(5., 0., 0.), Vec3::new(-2., 0., 0.));
+        assert_hit(hit, 1.25, Vec3::X);
+    }
+
+    #[test]
+    fn misses_through_the_hole() {
+        assert!(hit(Point3::new(0., 5., 0.), Vec3::NEG_Y).is_none());
+    }
+}
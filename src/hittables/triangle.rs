@@ -1,11 +1,18 @@
+use std::ops::{Add, Mul};
+
 use crate::{
     geometry::{Point3, Ray},
-    hittables::hittable::{HitRecord, Hittable},
+    hittables::{
+        aabb::Aabb,
+        hittable::{HitRecord, Hittable},
+        light::{Emitter, LightSample},
+    },
     interval::Interval,
-    textures::material::Material,
+    sampler::{Sample, Sampler},
+    textures::{material::Material, texture::GetTexture},
 };
 
-use glam::Vec3;
+use glam::{Vec2, Vec3};
 
 #[derive(Clone, Debug)]
 pub struct Triangle {
@@ -14,12 +21,27 @@ pub struct Triangle {
     corner_three: Point3,
     material: Material,
     normal: Vec3,
+    vertex_normals: Option<[Vec3; 3]>,
+    vertex_uvs: Option<[Vec2; 3]>,
+    bounding_box: Aabb,
+}
+
+/// Blends values at the three corners using the barycentric coordinates of
+/// the second and third corners
+pub(super) fn interpolate<T>([one, two, three]: [T; 3], u: f32, v: f32) -> T
+where
+    T: Add<Output = T> + Mul<f32, Output = T>,
+{
+    one * (1. - u - v) + two * u + three * v
 }
 
 impl Hittable for Triangle {
     fn was_hit(&self, ray: Ray, interval: Interval) -> Option<HitRecord> {
-        let (collision_time, u, v) =
-            self.moller_trumbore_intersection(ray.origin, ray.direction)?;
+        let (collision_time, u, v) = Self::moller_trumbore_intersection(
+            self.corners(),
+            ray.origin,
+            ray.direction,
+        )?;
 
         if !interval.surrounds(collision_time) {
             return None;
@@ -29,9 +51,16 @@ impl Hittable for Triangle {
 
         let front_face = self.normal.dot(ray.direction.normalize()) > 0.;
 
+        let normal = self
+            .vertex_normals
+            .map_or(self.normal, |normals| interpolate(normals, u, v));
+        let (u, v) = self
+            .vertex_uvs
+            .map_or((u, v), |uvs| interpolate(uvs, u, v).into());
+
         Some(HitRecord::new(
             collision_point,
-            self.normal,
+            normal,
             collision_time,
             front_face,
             self.material.clone(),
@@ -39,15 +68,81 @@ impl Hittable for Triangle {
             v,
         ))
     }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bounding_box
+    }
+}
+
+impl Emitter for Triangle {
+    fn is_emissive(&self) -> bool {
+        self.material.is_light
+    }
+
+    fn sample_from(
+        &self,
+        origin: Point3,
+        sampler: &mut Sampler,
+    ) -> Option<LightSample> {
+        // Uniform sampling over the triangle's area
+        let sample = sampler.get_2d();
+        let (root, second) = (sample.x.sqrt(), sample.y);
+        let (u, v) = (root * (1. - second), root * second);
+        let point = interpolate(self.corners(), u, v);
+
+        let to_point = *(point - origin);
+        let distance = to_point.length();
+        let direction = to_point / distance;
+        let pdf = self.area_to_solid_angle(direction, distance)?;
+        let (u, v) = self
+            .vertex_uvs
+            .map_or((u, v), |uvs| interpolate(uvs, u, v).into());
+        Some(LightSample {
+            direction,
+            distance,
+            pdf,
+            emitted: self.material.texture.get_colour(u, v),
+        })
+    }
+
+    fn pdf_from(&self, origin: Point3, direction: Vec3) -> f32 {
+        Self::moller_trumbore_intersection(self.corners(), origin, direction)
+            .and_then(|(distance, _, _)| {
+                self.area_to_solid_angle(direction, distance)
+            })
+            .unwrap_or(0.)
+    }
 }
 
 impl Triangle {
+    fn corners(&self) -> [Point3; 3] {
+        [self.corner_one, self.corner_two, self.corner_three]
+    }
+
+    /// Converts the uniform area density into a solid angle density for a
+    /// point `distance` away along the unit vector `direction`
+    fn area_to_solid_angle(
+        &self,
+        direction: Vec3,
+        distance: f32,
+    ) -> Option<f32> {
+        let cosine = self.normal.dot(direction).abs();
+        let area = 0.5
+            * (self.corner_two - self.corner_one)
+                .cross(*(self.corner_three - self.corner_one))
+                .length();
+        (cosine > f32::EPSILON && area > 0.)
+            .then(|| distance * distance / (cosine * area))
+    }
+
     pub fn new(
-        corner_one: Point3,
-        corner_two: Point3,
-        corner_three: Point3,
-        material: Material,
+        corner_one: impl Into<Point3>,
+        corner_two: impl Into<Point3>,
+        corner_three: impl Into<Point3>,
+        material: impl Into<Material>,
     ) -> Self {
+        let (corner_one, corner_two, corner_three) =
+            (corner_one.into(), corner_two.into(), corner_three.into());
         // https://stackoverflow.com/a/23709352/23247074
         let a_vector = *(corner_two - corner_one);
         let b_vector = *(corner_three - corner_one);
@@ -63,34 +158,55 @@ impl Triangle {
                 .max(corner_three[axis]);
             intervals[axis] = Interval::new(axis_min, axis_max);
         }
+        let [x, y, z] = intervals;
 
         Self {
             corner_one,
             corner_two,
             corner_three,
-            material,
+            material: material.into(),
             normal,
+            vertex_normals: None,
+            vertex_uvs: None,
+            bounding_box: Aabb::new(x, y, z),
         }
     }
 
+    pub fn with_material(mut self, material: impl Into<Material>) -> Self {
+        self.material = material.into();
+        self
+    }
+
+    /// Normals at each corner, interpolated across the face for smooth shading
+    pub fn with_vertex_normals(mut self, normals: [Vec3; 3]) -> Self {
+        self.vertex_normals = Some(normals.map(Vec3::normalize));
+        self
+    }
+
+    /// Texture coordinates at each corner, interpolated across the face
+    pub fn with_vertex_uvs(mut self, uvs: [Vec2; 3]) -> Self {
+        self.vertex_uvs = Some(uvs);
+        self
+    }
+
     //https://en.wikipedia.org/wiki/Möller-Trumbore_intersection_algorithm#Rust_implementation
-    fn moller_trumbore_intersection(
-        &self,
+    pub(super) fn moller_trumbore_intersection(
+        [corner_one, corner_two, corner_three]: [Point3; 3],
         origin: Point3,
         direction: Vec3,
     ) -> Option<(f32, f32, f32)> {
-        let e1 = self.corner_two - self.corner_one;
-        let e2 = self.corner_three - self.corner_one;
+        let e1 = corner_two - corner_one;
+        let e2 = corner_three - corner_one;
 
         let ray_cross_e2 = direction.cross(*e2);
         let det = e1.dot(ray_cross_e2);
 
         if det.abs() < f32::EPSILON {
-            return None; // This ray is parallel to this self.
+            return None; // This ray is parallel to this triangle.
         }
 
         let inv_det = 1.0 / det;
-        let s = origin - self.corner_one;
+        let s = origin - corner_one;
         let u = inv_det * s.dot(ray_cross_e2);
         if !(0.0..=1.0).contains(&u) {
             return None;
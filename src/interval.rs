@@ -7,6 +7,9 @@ pub struct Interval {
 }
 
 impl Interval {
+    pub const EMPTY: Self = Self::new(f32::INFINITY, f32::NEG_INFINITY);
+    pub const UNIVERSE: Self = Self::new(f32::NEG_INFINITY, f32::INFINITY);
+
     pub fn surrounds(self, value: f32) -> bool {
         self.min < value && value < self.max
     }
@@ -14,4 +17,17 @@ impl Interval {
     pub fn clamp(self, value: f32) -> f32 {
         value.clamp(self.min, self.max)
     }
+
+    pub fn size(self) -> f32 {
+        self.max - self.min
+    }
+
+    pub fn union(self, other: Self) -> Self {
+        Self::new(self.min.min(other.min), self.max.max(other.max))
+    }
+
+    pub fn expand(self, delta: f32) -> Self {
+        let padding = delta / 2.;
+        Self::new(self.min - padding, self.max + padding)
+    }
 }
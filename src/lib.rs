@@ -0,0 +1,83 @@
+//! A path tracer which renders scenes read from `.scene` files, or built up
+//! in code:
+//!
+//! ```no_run
+//! use ray_tracing::{
+//!     CameraSettings, Colour, Material, Point3, Profile, Scene, Sky, Sphere,
+//!     Texture, render,
+//! };
+//!
+//! let red = Material::from(Colour::new(0.8, 0.2, 0.2));
+//! let floor = Texture::checker(Colour::WHITE, Colour::BLACK, 0.1);
+//! let scene = Scene::new(
+//!     CameraSettings::new(Point3::new(0., 1., 5.), Point3::new(0., 0., 0.)),
+//!     Sky::new(Colour::new(0.5, 0.7, 1.), Colour::WHITE),
+//! )
+//! .with_object(Sphere::new(Point3::new(0., 0., 0.), 1., red))
+//! .with_object(Sphere::new(Point3::new(0., -101., 0.), 100., floor))
+//! .optimise();
+//! let image = render(&scene, &Profile::default(), false).image;
+//! ```
+
+mod adaptive;
+mod aov;
+mod camera;
+mod checkpoint;
+mod colour;
+mod denoise;
+mod film;
+mod filter;
+mod geometry;
+mod hittables;
+mod interval;
+mod output;
+mod profile;
+mod progress;
+mod random;
+mod sampler;
+mod scene;
+mod scene_reader;
+mod textures;
+mod tile;
+mod tone_mapping;
+
+pub use crate::{
+    aov::Aov,
+    checkpoint::{Checkpoint, CheckpointError, CheckpointSettings},
+    colour::Colour,
+    film::Frame,
+    filter::FilterKind,
+    geometry::{Point3, Ray},
+    hittables::{
+        aabb::Aabb,
+        capsule::Capsule,
+        cone::Cone,
+        cuboid::Cuboid,
+        cylinder::Cylinder,
+        disk::Disk,
+        hittable::{HitRecord, Hittable, HittableList, HittableObject},
+        instance::Instance,
+        mesh::Mesh,
+        plane::Plane,
+        quad::Quad,
+        sphere::Sphere,
+        torus::Torus,
+        triangle::Triangle,
+    },
+    interval::Interval,
+    output::{OutputFormat, save_aov, save_image},
+    profile::{Profile, ProfileError, ProfileOverrides, Profiles},
+    sampler::SamplerKind,
+    scene::{CameraSettings, Scene, Sky, render, render_from},
+    scene_reader::{
+        ValueError,
+        scene_parser::{SceneError, SceneErrors, read_scene},
+    },
+    textures::{
+        gradient_texture::Direction as GradientDirection,
+        image_texture::WrapMode, material::Material,
+        stripe_texture::Direction as StripeDirection, texture::Texture,
+    },
+    tile::TileOrder,
+    tone_mapping::{ToneMapper, ToneMapping},
+};
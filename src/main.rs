@@ -1,62 +1,520 @@
-mod camera;
-mod colour;
-mod file_utils;
-mod geometry;
-mod hittables;
-mod interval;
-mod scene_reader;
-mod textures;
-
-use crate::{
-    camera::Camera, file_utils::clean_scenes,
-    scene_reader::scene_parser::read_scene,
+use std::{
+    io,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    time::{Duration, Instant},
+};
+
+use clap::{Args, Parser, Subcommand};
+use image::ImageError;
+use ray_tracing::{
+    Aov, Checkpoint, CheckpointError, CheckpointSettings, FilterKind, Hittable,
+    HittableObject, OutputFormat, ProfileError, ProfileOverrides, Profiles,
+    SamplerKind, Scene, SceneErrors, TileOrder, ToneMapper, read_scene,
+    render_from, save_aov, save_image,
+};
+use thiserror::Error;
+
+use crate::file_utils::{
+    clean_scenes, find_unclean_scenes, line_diff, scene_paths,
 };
-use clap::Parser;
-use image::ImageResult;
 
-/// Program to render images from a `.scene` file
+mod file_utils;
+
+/// Program to render images from `.scene` files
 #[derive(Debug, Parser)]
 #[command(version, about, long_about = None)]
-struct Args {
-    /// Profile to render image at
-    #[arg(short, long, default_value = "clean_scenes")]
-    profile: String,
-
-    /// Which image to render
-    #[arg(short, long, default_value = "")]
-    scene: String,
-
-    /// Whether to print progress reports
-    #[arg(short, long, default_value_t = 10)]
-    report_count: u32,
-}
-
-fn main() -> ImageResult<()> {
-    let args = Args::parse();
-    let (profile, scene_name, progress_reports) =
-        (args.profile, args.scene, args.report_count);
-    let profile_data = match profile.as_str() {
-        "debug" => (800, 10, 10),
-        "release" => (800, 100, 10),
-        "insane" => (800, 1_000, 10),
-        "overnight" => (1_920, 5_000, 100),
-        "bounce" => (800, 100, 50),
-        "bounce_with_insane" => (1_920, 500, 50),
-        "clean_scenes" => {
-            clean_scenes();
-            return Ok(());
+struct Cli {
+    /// Directory to find scenes in
+    #[arg(long, global = true, default_value = "scenes")]
+    scenes_dir: PathBuf,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Render a scene
+    Render {
+        /// Which scene to render
+        #[arg(short, long)]
+        scene: String,
+
+        /// Profile to render the image at
+        #[arg(short, long, default_value = "release")]
+        profile: String,
+
+        /// Where to save the image, with the extension replaced by each
+        /// format's. Defaults to `<images-dir>/<scene>/<profile>`
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        #[command(flatten)]
+        options: RenderOptions,
+    },
+    /// Reformat every scene file
+    Fmt {
+        /// Print the changes formatting would make instead of making them
+        #[arg(long)]
+        check: bool,
+    },
+    /// Parse scenes and report any errors, without rendering them
+    Check {
+        /// Scenes to check, or every scene if none are given
+        scenes: Vec<String>,
+    },
+    /// Print the object, material and light counts and bounds of a scene
+    Info {
+        /// Which scene to describe
+        scene: String,
+    },
+    /// Render several scenes, each at several profiles
+    Batch {
+        /// Scenes to render, or every scene if none are given
+        scenes: Vec<String>,
+
+        /// Profiles to render each scene at, separated by commas
+        #[arg(short, long, value_delimiter = ',', default_value = "release")]
+        profiles: Vec<String>,
+
+        #[command(flatten)]
+        options: RenderOptions,
+    },
+}
+
+#[derive(Debug, Args)]
+struct RenderOptions {
+    /// Directory images are saved in by default
+    #[arg(long, default_value = "images")]
+    images_dir: PathBuf,
+
+    /// Don't show the progress bar or how long rendering took
+    #[arg(short, long)]
+    quiet: bool,
+
+    /// Formats to save the image in, separated by commas, overriding the
+    /// profile's
+    #[arg(short, long, value_enum, value_delimiter = ',')]
+    format: Option<Vec<OutputFormat>>,
+
+    /// Image width in pixels, overriding the profile's
+    #[arg(long)]
+    width: Option<u32>,
+
+    /// Samples per pixel, overriding the profile's
+    #[arg(long)]
+    samples: Option<u16>,
+
+    /// Maximum bounces per ray, overriding the profile's
+    #[arg(long)]
+    max_bounces: Option<u16>,
+
+    /// Random seed, overriding the profile's
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// How samples are spread over each pixel, overriding the profile's
+    #[arg(long, value_enum)]
+    sampler: Option<SamplerKind>,
+
+    /// Samples every pixel takes before adaptive sampling can stop it,
+    /// overriding the profile's
+    #[arg(long)]
+    min_samples: Option<u16>,
+
+    /// The most samples adaptive sampling gives any one pixel, overriding
+    /// the profile's
+    #[arg(long)]
+    max_samples: Option<u16>,
+
+    /// Noise adaptive sampling stops at, relative to each pixel's
+    /// brightness, overriding the profile's
+    #[arg(long)]
+    noise_threshold: Option<f32>,
+
+    /// Reconstruction filter samples are weighted by, overriding the
+    /// profile's
+    #[arg(long, value_enum)]
+    filter: Option<FilterKind>,
+
+    /// Filter radius in pixels, overriding the profile's and the filter's
+    /// own
+    #[arg(long)]
+    filter_radius: Option<f32>,
+
+    /// Denoise the image, whatever the profile says
+    #[arg(long)]
+    denoise: bool,
+
+    /// Feature buffers to save next to the image, separated by commas,
+    /// overriding the profile's. Each is saved in every format, with its
+    /// name before the extension.
+    #[arg(long, value_enum, value_delimiter = ',')]
+    aov: Option<Vec<Aov>>,
+
+    /// Samples each pixel takes per pass, overriding the profile's
+    #[arg(long)]
+    pass_samples: Option<u16>,
+
+    /// Seconds between saving checkpoints, overriding the profile's
+    #[arg(long)]
+    checkpoint_interval: Option<u64>,
+
+    /// Width and height of the tiles rendered at once, in pixels,
+    /// overriding the profile's
+    #[arg(long)]
+    tile_size: Option<u32>,
+
+    /// Order tiles are rendered in, overriding the profile's
+    #[arg(long, value_enum)]
+    tile_order: Option<TileOrder>,
+
+    /// Carry on from the checkpoint next to the image, if there is one,
+    /// saving it again once done
+    #[arg(long)]
+    resume: bool,
+
+    /// Tone mapping operator for PNG output, overriding the profile's and
+    /// the scene's
+    #[arg(short, long, value_enum)]
+    tone_map: Option<ToneMapper>,
+
+    /// Exposure in stops, overriding the scene's
+    #[arg(short, long, allow_negative_numbers = true)]
+    exposure: Option<f32>,
+
+    /// Radiance mapped to white by extended Reinhard, overriding the scene's
+    #[arg(short, long)]
+    white_point: Option<f32>,
+}
+
+impl RenderOptions {
+    fn overrides(&self) -> ProfileOverrides {
+        ProfileOverrides {
+            width: self.width,
+            samples: self.samples,
+            max_bounces: self.max_bounces,
+            tone_mapper: self.tone_map,
+            format: self.format.clone(),
+            seed: self.seed,
+            sampler: self.sampler,
+            min_samples: self.min_samples,
+            max_samples: self.max_samples,
+            noise_threshold: self.noise_threshold,
+            filter: self.filter,
+            filter_radius: self.filter_radius,
+            denoise: self.denoise.then_some(true),
+            aovs: self.aov.clone(),
+            pass_samples: self.pass_samples,
+            checkpoint_interval: self.checkpoint_interval,
+            tile_size: self.tile_size,
+            tile_order: self.tile_order,
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+enum CliError {
+    #[error(transparent)]
+    Profile(#[from] ProfileError),
+    #[error(transparent)]
+    Scene(#[from] SceneErrors),
+    #[error(transparent)]
+    Checkpoint(#[from] CheckpointError),
+    #[error(transparent)]
+    Image(#[from] ImageError),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+type CliResult = Result<ExitCode, CliError>;
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let scenes_dir = &cli.scenes_dir;
+    let result = match cli.command {
+        Command::Render {
+            scene,
+            profile,
+            output,
+            options,
+        } => render_command(scenes_dir, &scene, &profile, output, &options),
+        Command::Fmt { check } => fmt_command(scenes_dir, check),
+        Command::Check { scenes } => check_command(scenes_dir, scenes),
+        Command::Info { scene } => info_command(scenes_dir, &scene),
+        Command::Batch {
+            scenes,
+            profiles,
+            options,
+        } => batch_command(scenes_dir, scenes, &profiles, &options),
+    };
+    result.unwrap_or_else(|error| {
+        eprintln!("{error}");
+        ExitCode::FAILURE
+    })
+}
+
+fn scene_path(scenes_dir: &Path, scene_name: &str) -> PathBuf {
+    scenes_dir.join(format!("{}.scene", scene_name.to_ascii_lowercase()))
+}
+
+/// The names of the given scenes, or of every scene if there are none
+fn scene_names_or_all(
+    scenes_dir: &Path,
+    scenes: Vec<String>,
+) -> io::Result<Vec<String>> {
+    if !scenes.is_empty() {
+        return Ok(scenes);
+    }
+    Ok(scene_paths(scenes_dir)?
+        .iter()
+        .filter_map(|path| path.file_stem()?.to_str().map(str::to_owned))
+        .collect())
+}
+
+fn render_command(
+    scenes_dir: &Path,
+    scene_name: &str,
+    profile_name: &str,
+    output: Option<PathBuf>,
+    options: &RenderOptions,
+) -> CliResult {
+    let profiles = Profiles::load()?;
+    render_scene(
+        scenes_dir,
+        scene_name,
+        &profiles,
+        profile_name,
+        output,
+        options,
+    )?;
+    Ok(ExitCode::SUCCESS)
+}
+
+fn render_scene(
+    scenes_dir: &Path,
+    scene_name: &str,
+    profiles: &Profiles,
+    profile_name: &str,
+    output: Option<PathBuf>,
+    options: &RenderOptions,
+) -> Result<(), CliError> {
+    let profile = profiles.get(profile_name, options.overrides())?;
+    let scene = read_scene(&scene_path(scenes_dir, scene_name))?;
+
+    let mut tone_mapping = scene.tone_mapping.unwrap_or_default();
+    if let Some(operator) = profile.tone_mapper {
+        tone_mapping.operator = operator;
+    }
+    if let Some(exposure) = options.exposure {
+        tone_mapping.exposure = exposure;
+    }
+    if let Some(white_point) = options.white_point {
+        tone_mapping.white_point = white_point;
+    }
+
+    let output = output.unwrap_or_else(|| {
+        options
+            .images_dir
+            .join(scene_name)
+            .join(profile_name.to_ascii_lowercase())
+    });
+    if let Some(directory) = output.parent() {
+        std::fs::create_dir_all(directory)?;
+    }
+
+    let checkpoint_path = output.with_extension("checkpoint");
+    let settings = CheckpointSettings::new(&scene, &profile);
+    let mut checkpoint = if options.resume && checkpoint_path.exists() {
+        let checkpoint = Checkpoint::load(&checkpoint_path)?;
+        checkpoint.check(&settings)?;
+        println!(
+            "resuming {} from {} samples",
+            checkpoint_path.display(),
+            checkpoint.samples
+        );
+        checkpoint
+    } else {
+        if options.resume {
+            println!("no checkpoint at {}", checkpoint_path.display());
         }
-        _ => panic!("Invalid profile"),
+        Checkpoint::new(settings)
     };
-    let (world, camera_info) =
-        read_scene(format!("scenes/{}.scene", scene_name.to_ascii_lowercase()));
-    let camera = Camera::initialise(profile_data, camera_info);
-    let image = camera.render(&world, progress_reports);
-    let dir_path = format!(r"images\{scene_name}");
-    let path = format!(
-        r"{dir_path}\{}.png",
-        profile.to_owned().to_ascii_lowercase()
+    let interval = profile.checkpoint_interval.map(Duration::from_secs);
+    let mut last_saved = Instant::now();
+    let frame = render_from(
+        &scene,
+        &profile,
+        &mut checkpoint,
+        !options.quiet,
+        |checkpoint| {
+            if interval.is_none_or(|interval| last_saved.elapsed() < interval) {
+                return;
+            }
+            // A failed save shouldn't throw away the render, which can
+            // still be saved at the end
+            if let Err(error) = checkpoint.save(&checkpoint_path) {
+                eprintln!("{error}");
+            }
+            last_saved = Instant::now();
+        },
+    )?;
+    if interval.is_some() || options.resume {
+        checkpoint.save(&checkpoint_path)?;
+    }
+    for &format in &profile.format {
+        let path = output.with_extension(format.extension());
+        save_image(&frame.image, path, format, &tone_mapping)?;
+        for &aov in &profile.aovs {
+            let extension = format!("{}.{}", aov.name(), format.extension());
+            save_aov(&frame, aov, output.with_extension(extension), format)?;
+        }
+    }
+    Ok(())
+}
+
+fn fmt_command(scenes_dir: &Path, check: bool) -> CliResult {
+    if !check {
+        for scene in clean_scenes(scenes_dir)? {
+            println!("formatted {}", scene.path.display());
+        }
+        return Ok(ExitCode::SUCCESS);
+    }
+    let unclean = find_unclean_scenes(scenes_dir)?;
+    for scene in &unclean {
+        println!("{}", scene.path.display());
+        print!("{}", line_diff(&scene.original, &scene.cleaned));
+    }
+    Ok(if unclean.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+fn check_command(scenes_dir: &Path, scenes: Vec<String>) -> CliResult {
+    let mut failed = false;
+    for scene_name in scene_names_or_all(scenes_dir, scenes)? {
+        let path = scene_path(scenes_dir, &scene_name);
+        match read_scene(&path) {
+            Ok(_) => println!("{}: ok", path.display()),
+            Err(errors) => {
+                eprintln!("{errors}");
+                failed = true;
+            }
+        }
+    }
+    Ok(if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    })
+}
+
+fn info_command(scenes_dir: &Path, scene_name: &str) -> CliResult {
+    let path = scene_path(scenes_dir, scene_name);
+    let scene = read_scene(&path)?;
+    print_info(&path, &scene);
+    Ok(ExitCode::SUCCESS)
+}
+
+fn print_info(path: &Path, scene: &Scene) {
+    let objects = scene.world.objects();
+    let (mut meshes, mut mesh_faces, mut mesh_materials) = (0, 0, 0);
+    let mut counts = Vec::<(&str, usize)>::new();
+    for object in objects {
+        let kind = match object {
+            HittableObject::Sphere(_) => "spheres",
+            HittableObject::Triangle(_) => "triangles",
+            HittableObject::Plane(_) => "planes",
+            HittableObject::Quad(_) => "quads",
+            HittableObject::Disk(_) => "disks",
+            HittableObject::Cuboid(_) => "boxes",
+            HittableObject::Cylinder(_) => "cylinders",
+            HittableObject::Cone(_) => "cones",
+            HittableObject::Capsule(_) => "capsules",
+            HittableObject::Torus(_) => "tori",
+            HittableObject::Instance(_) => "instances",
+            HittableObject::Mesh(mesh) => {
+                meshes += 1;
+                mesh_faces += mesh.face_count();
+                mesh_materials += mesh.material_count();
+                continue;
+            }
+        };
+        match counts.iter_mut().find(|(name, _)| *name == kind) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((kind, 1)),
+        }
+    }
+    let bounds = scene.world.bounding_box();
+
+    // Only the kinds of object the scene actually has, most common first
+    counts.sort_by_key(|&(_, count)| std::cmp::Reverse(count));
+    let mut kinds = counts
+        .into_iter()
+        .map(|(kind, count)| format!("{count} {kind}"))
+        .collect::<Vec<_>>();
+    if meshes > 0 {
+        kinds.push(format!("{meshes} meshes with {mesh_faces} faces"));
+    }
+
+    println!("{}", path.display());
+    if kinds.is_empty() {
+        println!("objects: 0");
+    } else {
+        println!("objects: {} ({})", objects.len(), kinds.join(", "));
+    }
+    println!(
+        "materials: {} named, {mesh_materials} from meshes",
+        scene.materials.len()
     );
-    std::fs::create_dir_all(dir_path).unwrap();
-    image.save(path)
+    println!("lights: {}", scene.light_count());
+    if objects.is_empty() {
+        println!("bounds: empty");
+    } else if !bounds.is_bounded() {
+        println!("bounds: unbounded");
+    } else {
+        println!(
+            "bounds: ({}, {}, {}) to ({}, {}, {})",
+            bounds.x.min,
+            bounds.y.min,
+            bounds.z.min,
+            bounds.x.max,
+            bounds.y.max,
+            bounds.z.max
+        );
+    }
+}
+
+fn batch_command(
+    scenes_dir: &Path,
+    scenes: Vec<String>,
+    profile_names: &[String],
+    options: &RenderOptions,
+) -> CliResult {
+    let profiles = Profiles::load()?;
+    let mut failed = false;
+    for scene_name in scene_names_or_all(scenes_dir, scenes)? {
+        for profile_name in profile_names {
+            println!("rendering {scene_name} at {profile_name}");
+            if let Err(error) = render_scene(
+                scenes_dir,
+                &scene_name,
+                &profiles,
+                profile_name,
+                None,
+                options,
+            ) {
+                eprintln!("{error}");
+                failed = true;
+            }
+        }
+    }
+    Ok(if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    })
 }
@@ -0,0 +1,83 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use clap::ValueEnum;
+use image::{ImageFormat, ImageResult, Rgb32FImage};
+use serde::Deserialize;
+
+use crate::{
+    aov::Aov, colour::to_rgb_image, film::Frame, tone_mapping::ToneMapping,
+};
+
+#[derive(Clone, Copy, Debug, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum OutputFormat {
+    /// Tone mapped 8-bit sRGB PNG
+    Png,
+    /// Radiance RGBE
+    Hdr,
+    /// OpenEXR with 32-bit float channels
+    Exr,
+    /// Portable float map
+    Pfm,
+}
+
+impl OutputFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::Hdr => "hdr",
+            Self::Exr => "exr",
+            Self::Pfm => "pfm",
+        }
+    }
+}
+
+/// Saves `image`, tone mapping it only for low dynamic range formats
+pub fn save_image(
+    image: &Rgb32FImage,
+    path: impl AsRef<Path>,
+    format: OutputFormat,
+    tone_mapping: &ToneMapping,
+) -> ImageResult<()> {
+    match format {
+        OutputFormat::Png => to_rgb_image(image, tone_mapping).save(path),
+        OutputFormat::Hdr => image.save_with_format(path, ImageFormat::Hdr),
+        OutputFormat::Exr => image.save_with_format(path, ImageFormat::OpenExr),
+        OutputFormat::Pfm => write_pfm(image, path.as_ref()),
+    }
+}
+
+/// Saves one of `frame`'s feature buffers, made viewable for low dynamic
+/// range formats and kept as it is otherwise
+pub fn save_aov(
+    frame: &Frame,
+    aov: Aov,
+    path: impl AsRef<Path>,
+    format: OutputFormat,
+) -> ImageResult<()> {
+    let tone_mapping = ToneMapping::default();
+    match format {
+        OutputFormat::Png => {
+            save_image(&aov.visualise(frame), path, format, &tone_mapping)
+        }
+        _ => save_image(aov.image(frame), path, format, &tone_mapping),
+    }
+}
+
+fn write_pfm(image: &Rgb32FImage, path: &Path) -> ImageResult<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    // A negative scale marks the data as little-endian
+    write!(file, "PF\n{} {}\n-1.0\n", image.width(), image.height())?;
+    // Rows are stored bottom to top
+    for row in image.rows().rev() {
+        for channel in row.flat_map(|pixel| pixel.0) {
+            file.write_all(&channel.to_le_bytes())?;
+        }
+    }
+    file.flush()?;
+    Ok(())
+}
@@ -0,0 +1,284 @@
+use std::{
+    collections::HashMap,
+    env, fs, io,
+    iter::once,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::{
+    adaptive::AdaptiveSampling,
+    aov::Aov,
+    filter::{Filter, FilterKind},
+    output::OutputFormat,
+    sampler::SamplerKind,
+    tile::TileOrder,
+    tone_mapping::ToneMapper,
+};
+
+const BUILT_IN_PROFILES: &str = include_str!("profiles.toml");
+const PROFILES_FILE_NAME: &str = "profiles.toml";
+
+#[derive(Debug, Error)]
+pub enum ProfileError {
+    #[error("unable to read {path:?}: {source}")]
+    Io { path: String, source: io::Error },
+    #[error("{path:?} is not a valid profiles file: {source}")]
+    Parse {
+        path: String,
+        source: toml::de::Error,
+    },
+    #[error("{name:?} is not a known profile - expected one of {}", known.join(", "))]
+    Unknown { name: String, known: Vec<String> },
+}
+
+type ProfileResult<T> = Result<T, ProfileError>;
+
+/// Render settings where any field may be left unset, so that it falls back
+/// on an earlier source
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProfileOverrides {
+    pub width: Option<u32>,
+    pub samples: Option<u16>,
+    pub max_bounces: Option<u16>,
+    pub tone_mapper: Option<ToneMapper>,
+    pub format: Option<Vec<OutputFormat>>,
+    pub seed: Option<u64>,
+    pub sampler: Option<SamplerKind>,
+    pub min_samples: Option<u16>,
+    pub max_samples: Option<u16>,
+    pub noise_threshold: Option<f32>,
+    pub filter: Option<FilterKind>,
+    pub filter_radius: Option<f32>,
+    pub denoise: Option<bool>,
+    pub aovs: Option<Vec<Aov>>,
+    pub pass_samples: Option<u16>,
+    pub checkpoint_interval: Option<u64>,
+    pub tile_size: Option<u32>,
+    pub tile_order: Option<TileOrder>,
+}
+
+impl ProfileOverrides {
+    /// Replaces each field that is set in `other`
+    pub fn merge(&mut self, other: Self) {
+        self.width = other.width.or(self.width);
+        self.samples = other.samples.or(self.samples);
+        self.max_bounces = other.max_bounces.or(self.max_bounces);
+        self.tone_mapper = other.tone_mapper.or(self.tone_mapper);
+        self.format = other.format.or(self.format.take());
+        self.seed = other.seed.or(self.seed);
+        self.sampler = other.sampler.or(self.sampler);
+        self.min_samples = other.min_samples.or(self.min_samples);
+        self.max_samples = other.max_samples.or(self.max_samples);
+        self.noise_threshold = other.noise_threshold.or(self.noise_threshold);
+        self.filter = other.filter.or(self.filter);
+        self.filter_radius = other.filter_radius.or(self.filter_radius);
+        self.denoise = other.denoise.or(self.denoise);
+        self.aovs = other.aovs.or(self.aovs.take());
+        self.pass_samples = other.pass_samples.or(self.pass_samples);
+        self.checkpoint_interval =
+            other.checkpoint_interval.or(self.checkpoint_interval);
+        self.tile_size = other.tile_size.or(self.tile_size);
+        self.tile_order = other.tile_order.or(self.tile_order);
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Profile {
+    pub width: u32,
+    pub samples: u16,
+    pub max_bounces: u16,
+    /// Overrides the scene's tone mapping operator when set
+    pub tone_mapper: Option<ToneMapper>,
+    pub format: Vec<OutputFormat>,
+    /// Renders with the same seed and settings come out identical
+    pub seed: u64,
+    pub sampler: SamplerKind,
+    /// Turns on adaptive sampling when set, with `samples` as the average
+    /// each pixel takes
+    pub min_samples: Option<u16>,
+    /// The most samples adaptive sampling gives any one pixel when handing
+    /// out those that converged pixels didn't need, or four times `samples`
+    /// if not set
+    pub max_samples: Option<u16>,
+    /// How precise adaptive sampling makes each pixel, as a fraction of its
+    /// luminance
+    pub noise_threshold: f32,
+    pub filter: FilterKind,
+    /// Overrides the filter's own radius when set, in pixels
+    pub filter_radius: Option<f32>,
+    pub denoise: bool,
+    /// Feature buffers to save next to the image
+    pub aovs: Vec<Aov>,
+    /// Samples each pixel takes per pass, or all of them in one pass if not
+    /// set
+    pub pass_samples: Option<u16>,
+    /// Seconds between saving checkpoints, which are only saved when set
+    pub checkpoint_interval: Option<u64>,
+    /// Width and height of the tiles the image is rendered in, in pixels
+    pub tile_size: u32,
+    pub tile_order: TileOrder,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self {
+            width: 800,
+            samples: 100,
+            max_bounces: 10,
+            tone_mapper: None,
+            format: vec![OutputFormat::Png],
+            seed: 0,
+            sampler: SamplerKind::default(),
+            min_samples: None,
+            max_samples: None,
+            noise_threshold: 0.01,
+            filter: FilterKind::default(),
+            filter_radius: None,
+            denoise: false,
+            aovs: Vec::new(),
+            pass_samples: None,
+            checkpoint_interval: None,
+            tile_size: 16,
+            tile_order: TileOrder::default(),
+        }
+    }
+}
+
+impl From<ProfileOverrides> for Profile {
+    fn from(overrides: ProfileOverrides) -> Self {
+        let default = Self::default();
+        Self {
+            width: overrides.width.unwrap_or(default.width),
+            samples: overrides.samples.unwrap_or(default.samples),
+            max_bounces: overrides.max_bounces.unwrap_or(default.max_bounces),
+            tone_mapper: overrides.tone_mapper,
+            format: overrides.format.unwrap_or(default.format),
+            seed: overrides.seed.unwrap_or(default.seed),
+            sampler: overrides.sampler.unwrap_or(default.sampler),
+            min_samples: overrides.min_samples,
+            max_samples: overrides.max_samples,
+            noise_threshold: overrides
+                .noise_threshold
+                .unwrap_or(default.noise_threshold),
+            filter: overrides.filter.unwrap_or(default.filter),
+            filter_radius: overrides.filter_radius,
+            denoise: overrides.denoise.unwrap_or(default.denoise),
+            aovs: overrides.aovs.unwrap_or(default.aovs),
+            pass_samples: overrides.pass_samples,
+            checkpoint_interval: overrides.checkpoint_interval,
+            tile_size: overrides.tile_size.unwrap_or(default.tile_size),
+            tile_order: overrides.tile_order.unwrap_or(default.tile_order),
+        }
+    }
+}
+
+impl Profile {
+    pub fn adaptive(&self) -> Option<AdaptiveSampling> {
+        let min_samples = self.min_samples?;
+        (min_samples < self.samples).then_some(AdaptiveSampling {
+            min_samples,
+            max_samples: self
+                .max_samples
+                .unwrap_or(self.samples.saturating_mul(4))
+                .max(self.samples),
+            noise_threshold: self.noise_threshold,
+        })
+    }
+
+    /// The most samples any one pixel can take
+    pub fn max_pixel_samples(&self) -> u16 {
+        self.adaptive()
+            .map_or(self.samples, |adaptive| adaptive.max_samples)
+    }
+
+    pub fn filter(&self) -> Filter {
+        Filter::new(self.filter, self.filter_radius)
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ProfilesFile {
+    #[serde(default)]
+    profiles: HashMap<String, ProfileOverrides>,
+}
+
+/// Named profiles gathered from every profiles file
+#[derive(Debug, Default)]
+pub struct Profiles(HashMap<String, ProfileOverrides>);
+
+impl Profiles {
+    /// Reads the built-in profiles, then the user's, then those of the
+    /// current directory, with each file overriding individual fields of the
+    /// ones before
+    pub fn load() -> ProfileResult<Self> {
+        let mut profiles = Self::default();
+        profiles.merge_str(BUILT_IN_PROFILES, "built-in profiles")?;
+        let paths = user_profiles_path()
+            .into_iter()
+            .chain(once(PathBuf::from(PROFILES_FILE_NAME)));
+        for path in paths {
+            profiles.merge_file(&path)?;
+        }
+        Ok(profiles)
+    }
+
+    /// Merges in the profiles of the file at `path`, if it exists
+    pub fn merge_file(&mut self, path: &Path) -> ProfileResult<()> {
+        match fs::read_to_string(path) {
+            Ok(text) => self.merge_str(&text, &path.display().to_string()),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(()),
+            Err(source) => Err(ProfileError::Io {
+                path: path.display().to_string(),
+                source,
+            }),
+        }
+    }
+
+    fn merge_str(&mut self, text: &str, path: &str) -> ProfileResult<()> {
+        let file = toml::from_str::<ProfilesFile>(text).map_err(|source| {
+            ProfileError::Parse {
+                path: path.to_owned(),
+                source,
+            }
+        })?;
+        for (name, overrides) in file.profiles {
+            self.0.entry(name).or_default().merge(overrides);
+        }
+        Ok(())
+    }
+
+    /// The profile called `name`, with `overrides` applied on top
+    pub fn get(
+        &self,
+        name: &str,
+        overrides: ProfileOverrides,
+    ) -> ProfileResult<Profile> {
+        let Some(profile) = self.0.get(name) else {
+            let mut known = self.0.keys().cloned().collect::<Vec<_>>();
+            known.sort_unstable();
+            return Err(ProfileError::Unknown {
+                name: name.to_owned(),
+                known,
+            });
+        };
+        let mut profile = profile.clone();
+        profile.merge(overrides);
+        Ok(profile.into())
+    }
+}
+
+/// `ray_tracing/profiles.toml` within the user's config directory
+fn user_profiles_path() -> Option<PathBuf> {
+    let config_dir = env::var_os("XDG_CONFIG_HOME")
+        .or_else(|| env::var_os("APPDATA"))
+        .map(PathBuf::from)
+        .or_else(|| {
+            env::var_os("HOME").map(|home| Path::new(&home).join(".config"))
+        })?;
+    Some(config_dir.join("ray_tracing").join(PROFILES_FILE_NAME))
+}
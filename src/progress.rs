@@ -0,0 +1,117 @@
+use std::{
+    io::{IsTerminal, stderr},
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, Instant},
+};
+
+/// Characters the bar itself takes up
+const BAR_WIDTH: usize = 30;
+
+/// Counts finished tiles from any thread without locking, and shows how far
+/// a render has got on a single line of standard error
+#[derive(Debug)]
+pub struct Progress {
+    tiles: u64,
+    done: AtomicU64,
+    /// Time spent rendering finished tiles, in nanoseconds, summed over
+    /// every thread
+    tile_time: AtomicU64,
+    slowest_tile: AtomicU64,
+    start: Instant,
+    threads: u64,
+    show: bool,
+    /// Whether the bar is redrawn, which would only make a mess of output
+    /// that isn't going to a terminal
+    draw: bool,
+}
+
+impl Progress {
+    /// Progress through `tiles` tiles, which is only shown if `show` is set
+    pub fn new(tiles: u64, show: bool) -> Self {
+        Self {
+            tiles,
+            done: AtomicU64::new(0),
+            tile_time: AtomicU64::new(0),
+            slowest_tile: AtomicU64::new(0),
+            start: Instant::now(),
+            threads: rayon::current_num_threads() as u64,
+            show,
+            draw: show && stderr().is_terminal(),
+        }
+    }
+
+    /// Records a tile that took `time` to render
+    pub fn tile_done(&self, time: Duration) {
+        let nanos = time.as_nanos() as u64;
+        self.tile_time.fetch_add(nanos, Ordering::Relaxed);
+        self.slowest_tile.fetch_max(nanos, Ordering::Relaxed);
+        let done = self.done.fetch_add(1, Ordering::Relaxed) + 1;
+        if self.draw {
+            self.draw_bar(done);
+        }
+    }
+
+    /// Average time taken by the tiles finished so far
+    fn average_tile(&self, done: u64) -> Duration {
+        Duration::from_nanos(
+            self.tile_time.load(Ordering::Relaxed) / done.max(1),
+        )
+    }
+
+    /// Time left if the remaining tiles take as long as the average one,
+    /// with every thread busy
+    fn eta(&self, done: u64) -> Duration {
+        let waves = (self.tiles - done).div_ceil(self.threads.max(1));
+        self.average_tile(done) * waves as u32
+    }
+
+    fn draw_bar(&self, done: u64) {
+        let fraction = done as f64 / self.tiles.max(1) as f64;
+        let filled = (fraction * BAR_WIDTH as f64) as usize;
+        eprint!(
+            "\r[{}{}] {:3.0}% {done}/{} tiles, {} elapsed, about {} left   ",
+            "#".repeat(filled),
+            "-".repeat(BAR_WIDTH - filled),
+            100. * fraction,
+            self.tiles,
+            format_duration(self.start.elapsed()),
+            format_duration(self.eta(done)),
+        );
+    }
+
+    /// Finishes off the bar and prints how long the tiles took
+    pub fn finish(&self) {
+        if !self.show {
+            return;
+        }
+        let done = self.done.load(Ordering::Relaxed);
+        if self.draw {
+            self.draw_bar(done);
+            eprintln!();
+        }
+        eprintln!(
+            "rendered {done} tiles in {}, taking {} on average and {} at most",
+            format_duration(self.start.elapsed()),
+            format_duration(self.average_tile(done)),
+            format_duration(Duration::from_nanos(
+                self.slowest_tile.load(Ordering::Relaxed)
+            )),
+        );
+    }
+}
+
+/// `duration` in hours, minutes and seconds, leaving out any that are zero
+/// at the front, or in milliseconds if it's under a second
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    let (hours, minutes) = (seconds / 3600, seconds / 60 % 60);
+    if hours > 0 {
+        format!("{hours}h {minutes:02}m {:02}s", seconds % 60)
+    } else if minutes > 0 {
+        format!("{minutes}m {:02}s", seconds % 60)
+    } else if seconds > 0 {
+        format!("{:.1}s", duration.as_secs_f32())
+    } else {
+        format!("{}ms", duration.as_millis())
+    }
+}
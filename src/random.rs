@@ -0,0 +1,26 @@
+use rand::SeedableRng;
+use rand_pcg::Pcg64Mcg;
+
+/// Generator used while rendering, picked for giving the same output on
+/// every platform
+pub type RenderRng = Pcg64Mcg;
+
+/// SplitMix64's finaliser, which spreads similar inputs across all 64 bits
+pub(crate) fn mix(value: u64) -> u64 {
+    let value = value.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let value = (value ^ (value >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    let value = (value ^ (value >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    value ^ (value >> 31)
+}
+
+/// A hash of the seed and pixel, shared by all of the pixel's samples
+pub(crate) fn pixel_hash(seed: u64, (i, j): (u32, u32)) -> u64 {
+    let pixel = u64::from(i) | (u64::from(j) << 32);
+    mix(mix(seed) ^ pixel)
+}
+
+/// An independent stream for one sample of one pixel, so that the image
+/// doesn't depend on which thread renders what
+pub fn sample_rng(seed: u64, pixel: (u32, u32), sample: u32) -> RenderRng {
+    RenderRng::seed_from_u64(mix(pixel_hash(seed, pixel) ^ u64::from(sample)))
+}
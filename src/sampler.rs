@@ -0,0 +1,319 @@
+use clap::ValueEnum;
+use enum_dispatch::enum_dispatch;
+use glam::Vec2;
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::random::{RenderRng, mix, pixel_hash, sample_rng};
+
+/// The largest `f32` below one
+const ONE_MINUS_EPSILON: f32 = 1. - f32::EPSILON / 2.;
+
+/// Bases of the Halton sequence's dimensions, after which it falls back on
+/// independent samples
+const PRIMES: [u64; 64] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71,
+    73, 79, 83, 89, 97, 101, 103, 107, 109, 113, 127, 131, 137, 139, 149, 151,
+    157, 163, 167, 173, 179, 181, 191, 193, 197, 199, 211, 223, 227, 229, 233,
+    239, 241, 251, 257, 263, 269, 271, 277, 281, 283, 293, 307, 311,
+];
+
+/// How the numbers behind each of a pixel's samples are spread out
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum SamplerKind {
+    /// Independent uniform random numbers
+    #[default]
+    Independent,
+    /// Jittered strata, shuffled independently for each dimension
+    Stratified,
+    /// The Halton sequence, with Owen scrambling
+    Halton,
+    /// Pairs of Sobol' dimensions, with Owen scrambling
+    Sobol,
+}
+
+impl SamplerKind {
+    /// Whether a pixel's first samples are the same whatever their total,
+    /// so that a render can be carried on to more of them
+    pub fn is_progressive(self) -> bool {
+        !matches!(self, Self::Stratified)
+    }
+
+    /// The sampler for sample `sample` of `samples_per_pixel` at `pixel`
+    pub fn start(
+        self,
+        seed: u64,
+        pixel: (u32, u32),
+        sample: u32,
+        samples_per_pixel: u32,
+    ) -> Sampler {
+        assert!(
+            sample < samples_per_pixel,
+            "sample {sample} is out of range for {samples_per_pixel} samples"
+        );
+        let rng = sample_rng(seed, pixel, sample);
+        let stream = Stream {
+            pixel_hash: pixel_hash(seed, pixel),
+            sample,
+            samples_per_pixel,
+            dimension: 0,
+        };
+        match self {
+            Self::Independent => IndependentSampler { rng }.into(),
+            Self::Stratified => StratifiedSampler {
+                grid: grid(samples_per_pixel),
+                stream,
+                rng,
+            }
+            .into(),
+            Self::Halton => HaltonSampler { stream, rng }.into(),
+            Self::Sobol => SobolSampler { stream }.into(),
+        }
+    }
+}
+
+/// Source of the numbers in [0, 1) that a sample's camera ray, lens and
+/// bounces are built from. Each call moves on to the next dimension, so
+/// callers should ask for the same dimensions in the same order for every
+/// sample of a pixel.
+#[enum_dispatch]
+pub trait Sample {
+    fn get_1d(&mut self) -> f32;
+    fn get_2d(&mut self) -> Vec2;
+}
+
+#[enum_dispatch(Sample)]
+#[derive(Clone, Debug)]
+pub enum Sampler {
+    Independent(IndependentSampler),
+    Stratified(StratifiedSampler),
+    Halton(HaltonSampler),
+    Sobol(SobolSampler),
+}
+
+/// Where a sampler is up to within its pixel
+#[derive(Clone, Debug)]
+struct Stream {
+    pixel_hash: u64,
+    sample: u32,
+    samples_per_pixel: u32,
+    dimension: u32,
+}
+
+impl Stream {
+    /// A hash of the pixel and the next dimension, which is the same for
+    /// every sample of the pixel
+    fn next_hash(&mut self) -> u64 {
+        let hash = mix(self.pixel_hash ^ u64::from(self.dimension));
+        self.dimension += 1;
+        hash
+    }
+
+    /// The sample's position in a shuffle of the pixel's samples
+    fn shuffled_sample(&self, hash: u64) -> u32 {
+        permutation_element(self.sample, self.samples_per_pixel, hash as u32)
+    }
+
+    /// The sample's number with its bits scrambled, which for every power
+    /// of two sends the first that many samples to an aligned block of that
+    /// many numbers. Unlike a shuffle, it doesn't depend on the total.
+    fn scrambled_sample(&self, hash: u64) -> u32 {
+        owen_scramble(self.sample, hash as u32)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct IndependentSampler {
+    rng: RenderRng,
+}
+
+impl Sample for IndependentSampler {
+    fn get_1d(&mut self) -> f32 {
+        self.rng.random()
+    }
+
+    fn get_2d(&mut self) -> Vec2 {
+        Vec2::new(self.rng.random(), self.rng.random())
+    }
+}
+
+/// Puts each sample in its own stratum, with 2D strata laid out in the
+/// squarest grid that the sample count divides into
+#[derive(Clone, Debug)]
+pub struct StratifiedSampler {
+    grid: (u32, u32),
+    stream: Stream,
+    rng: RenderRng,
+}
+
+impl Sample for StratifiedSampler {
+    fn get_1d(&mut self) -> f32 {
+        let hash = self.stream.next_hash();
+        let stratum = self.stream.shuffled_sample(hash);
+        let offset = self.rng.random::<f32>();
+        ((stratum as f32 + offset) / self.stream.samples_per_pixel as f32)
+            .min(ONE_MINUS_EPSILON)
+    }
+
+    fn get_2d(&mut self) -> Vec2 {
+        let hash = self.stream.next_hash();
+        let stratum = self.stream.shuffled_sample(hash);
+        let (columns, rows) = self.grid;
+        let offset = Vec2::new(self.rng.random(), self.rng.random());
+        let cell =
+            Vec2::new((stratum % columns) as f32, (stratum / columns) as f32);
+        ((cell + offset) / Vec2::new(columns as f32, rows as f32))
+            .min(Vec2::splat(ONE_MINUS_EPSILON))
+    }
+}
+
+/// The columns and rows of the squarest grid with `count` cells
+fn grid(count: u32) -> (u32, u32) {
+    let columns = (1..=count.isqrt())
+        .rev()
+        .find(|columns| count.is_multiple_of(*columns))
+        .unwrap_or(1);
+    (columns, count / columns)
+}
+
+/// Gives each pixel its own scrambling of the first points of the Halton
+/// sequence
+#[derive(Clone, Debug)]
+pub struct HaltonSampler {
+    stream: Stream,
+    rng: RenderRng,
+}
+
+impl Sample for HaltonSampler {
+    fn get_1d(&mut self) -> f32 {
+        let dimension = self.stream.dimension as usize;
+        let hash = self.stream.next_hash();
+        match PRIMES.get(dimension) {
+            Some(&base) => owen_scrambled_radical_inverse(
+                base,
+                u64::from(self.stream.sample),
+                hash,
+            ),
+            None => self.rng.random(),
+        }
+    }
+
+    fn get_2d(&mut self) -> Vec2 {
+        Vec2::new(self.get_1d(), self.get_1d())
+    }
+}
+
+/// Takes each dimension, or pair of dimensions, from the first one or two
+/// dimensions of the Sobol' sequence, with the pixel's sample numbers
+/// scrambled between them so they aren't correlated
+#[derive(Clone, Debug)]
+pub struct SobolSampler {
+    stream: Stream,
+}
+
+impl Sample for SobolSampler {
+    fn get_1d(&mut self) -> f32 {
+        let hash = self.stream.next_hash();
+        let index = self.stream.scrambled_sample(hash);
+        to_unit(owen_scramble(index.reverse_bits(), (hash >> 32) as u32))
+    }
+
+    fn get_2d(&mut self) -> Vec2 {
+        let hash = self.stream.next_hash();
+        let index = self.stream.scrambled_sample(hash);
+        let mixed = mix(hash);
+        Vec2::new(
+            to_unit(owen_scramble(index.reverse_bits(), mixed as u32)),
+            to_unit(owen_scramble(sobol_second(index), (mixed >> 32) as u32)),
+        )
+    }
+}
+
+/// The second dimension of the Sobol' sequence, whose direction numbers
+/// each come from the last by `v ^ (v >> 1)`
+fn sobol_second(mut index: u32) -> u32 {
+    let mut result = 0;
+    let mut direction = 1 << 31;
+    while index != 0 {
+        if index & 1 != 0 {
+            result ^= direction;
+        }
+        index >>= 1;
+        direction ^= direction >> 1;
+    }
+    result
+}
+
+/// Burley's hash-based nested uniform scramble of a fixed point value
+fn owen_scramble(value: u32, seed: u32) -> u32 {
+    let mut value = value.reverse_bits();
+    value ^= value.wrapping_mul(0x3d20_adea);
+    value = value.wrapping_add(seed);
+    value = value.wrapping_mul((seed >> 16) | 1);
+    value ^= value.wrapping_mul(0x0552_6c56);
+    value ^= value.wrapping_mul(0x53a2_2864);
+    value.reverse_bits()
+}
+
+/// The digits of `index` in `base` mirrored about the radix point, with
+/// each digit shifted by a hash of the digits before it
+fn owen_scrambled_radical_inverse(base: u64, mut index: u64, hash: u64) -> f32 {
+    let inverse_base = 1. / base as f32;
+    let mut inverse_base_power = 1.;
+    let mut reversed = 0;
+    // Carry on past the last digit of `index`, as its zeros are scrambled too
+    while 1. - (base - 1) as f32 * inverse_base_power < 1. {
+        let digit = index % base;
+        let shift = mix(hash ^ reversed) % base;
+        reversed = reversed * base + (digit + shift) % base;
+        inverse_base_power *= inverse_base;
+        index /= base;
+    }
+    (reversed as f32 * inverse_base_power).min(ONE_MINUS_EPSILON)
+}
+
+/// Kensler's hashed permutation, giving where `index` is sent by a shuffle
+/// of `0..length` chosen by `seed`. `index` must be below `length`, or the
+/// walk around the permutation's cycle might never come back into range
+fn permutation_element(mut index: u32, length: u32, seed: u32) -> u32 {
+    debug_assert!(index < length);
+    if length <= 1 {
+        return 0;
+    }
+    let mut mask = length - 1;
+    mask |= mask >> 1;
+    mask |= mask >> 2;
+    mask |= mask >> 4;
+    mask |= mask >> 8;
+    mask |= mask >> 16;
+    loop {
+        index ^= seed;
+        index = index.wrapping_mul(0xe170_893d);
+        index ^= seed >> 16;
+        index ^= (index & mask) >> 4;
+        index ^= seed >> 8;
+        index = index.wrapping_mul(0x0929_eb3f);
+        index ^= seed >> 23;
+        index ^= (index & mask) >> 1;
+        index = index.wrapping_mul(1 | seed >> 27);
+        index = index.wrapping_mul(0x6935_fa69);
+        index ^= (index & mask) >> 11;
+        index = index.wrapping_mul(0x74dc_b303);
+        index ^= (index & mask) >> 2;
+        index = index.wrapping_mul(0x9e50_1cc3);
+        index ^= (index & mask) >> 2;
+        index = index.wrapping_mul(0xc860_a3df);
+        index &= mask;
+        index ^= index >> 5;
+        if index < length {
+            break;
+        }
+    }
+    index.wrapping_add(seed) % length
+}
+
+/// Maps 32 fixed point bits into [0, 1), keeping the 24 that fit in an `f32`
+fn to_unit(bits: u32) -> f32 {
+    (bits >> 8) as f32 / (1 << 24) as f32
+}
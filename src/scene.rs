@@ -0,0 +1,205 @@
+use std::collections::HashMap;
+
+use crate::{
+    camera::Camera,
+    checkpoint::{Checkpoint, CheckpointError, CheckpointSettings},
+    colour::Colour,
+    denoise::denoise,
+    film::Frame,
+    geometry::Point3,
+    hittables::{
+        hittable::{HittableList, HittableObject},
+        light::LightList,
+    },
+    profile::Profile,
+    textures::material::Material,
+    tone_mapping::ToneMapping,
+};
+
+/// Where the camera sits and how it sees the world
+#[derive(Copy, Clone, Debug)]
+pub struct CameraSettings {
+    pub look_from: Point3,
+    pub look_at: Point3,
+    /// Roll around the viewing direction, in degrees
+    pub tilt: f32,
+    /// Vertical field of view, in degrees
+    pub fov: f32,
+    pub aspect_ratio: f32,
+    pub focus_distance: f32,
+    /// Angle of the cone of rays through each pixel, in degrees
+    pub defocus_angle: f32,
+}
+
+impl CameraSettings {
+    /// A pinhole camera with a 90 degree field of view and a 16:9 aspect
+    /// ratio, focused on `look_at`
+    pub fn new(look_from: Point3, look_at: Point3) -> Self {
+        Self {
+            look_from,
+            look_at,
+            tilt: 0.,
+            fov: 90.,
+            aspect_ratio: 16. / 9.,
+            focus_distance: (look_at - look_from).length(),
+            defocus_angle: 0.,
+        }
+    }
+
+    pub fn with_tilt(mut self, tilt: f32) -> Self {
+        self.tilt = tilt;
+        self
+    }
+
+    pub fn with_fov(mut self, fov: f32) -> Self {
+        self.fov = fov;
+        self
+    }
+
+    pub fn with_aspect_ratio(mut self, aspect_ratio: f32) -> Self {
+        self.aspect_ratio = aspect_ratio;
+        self
+    }
+
+    /// Height of an image `width` pixels wide
+    pub fn image_height(&self, width: u32) -> u32 {
+        (width as f32 / self.aspect_ratio).floor() as u32
+    }
+
+    pub fn with_defocus(
+        mut self,
+        focus_distance: f32,
+        defocus_angle: f32,
+    ) -> Self {
+        self.focus_distance = focus_distance;
+        self.defocus_angle = defocus_angle;
+        self
+    }
+}
+
+/// Vertical gradient seen by rays that escape the world
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Sky {
+    pub top: Colour,
+    pub bottom: Colour,
+}
+
+impl Sky {
+    pub const fn new(top: Colour, bottom: Colour) -> Self {
+        Self { top, bottom }
+    }
+}
+
+#[derive(Debug)]
+pub struct Scene {
+    pub world: HittableList,
+    pub camera: CameraSettings,
+    pub sky: Sky,
+    /// The scene's preferred tone mapping, if it has one
+    pub tone_mapping: Option<ToneMapping>,
+    /// Materials the scene file defined by name
+    pub materials: HashMap<String, Material>,
+    /// A hash of the scene file's text, and the paths and modification
+    /// times of the files it read, or `None` for scenes built in code
+    pub(crate) source_hash: Option<u64>,
+}
+
+impl Scene {
+    pub fn new(camera: CameraSettings, sky: Sky) -> Self {
+        Self {
+            world: HittableList::default(),
+            camera,
+            sky,
+            tone_mapping: None,
+            materials: HashMap::new(),
+            source_hash: None,
+        }
+    }
+
+    pub fn with_object(mut self, object: impl Into<HittableObject>) -> Self {
+        self.world.push(object.into());
+        self
+    }
+
+    pub fn with_objects(
+        mut self,
+        objects: impl IntoIterator<Item = HittableObject>,
+    ) -> Self {
+        objects
+            .into_iter()
+            .for_each(|object| self.world.push(object));
+        self
+    }
+
+    pub fn with_tone_mapping(mut self, tone_mapping: ToneMapping) -> Self {
+        self.tone_mapping = Some(tone_mapping);
+        self
+    }
+
+    /// Builds the world's bounding volume hierarchy, which rendering relies
+    /// on to be quick once objects have been added
+    pub fn optimise(mut self) -> Self {
+        self.world = self.world.optimise();
+        self
+    }
+
+    /// How many lights are sampled directly, with each face of a glowing
+    /// box counting as one
+    pub fn light_count(&self) -> usize {
+        LightList::from_objects(self.world.objects()).len()
+    }
+}
+
+/// Renders `scene` into a linear framebuffer along with the features of
+/// what each pixel saw, showing its progress if `show_progress` is set, and
+/// denoising the image if the profile asks for that
+pub fn render(scene: &Scene, profile: &Profile, show_progress: bool) -> Frame {
+    let mut checkpoint =
+        Checkpoint::new(CheckpointSettings::new(scene, profile));
+    render_checkpoint(scene, profile, &mut checkpoint, show_progress, |_| {})
+}
+
+/// Renders `scene` as `render` does, but carrying on from `checkpoint`.
+/// The checkpoint is given to `between_passes` after each pass but the
+/// last, so that it can be saved, and is left holding the finished render.
+/// Fails if the checkpoint was saved from a different scene or settings,
+/// or its buffers aren't the size it claims.
+pub fn render_from(
+    scene: &Scene,
+    profile: &Profile,
+    checkpoint: &mut Checkpoint,
+    show_progress: bool,
+    between_passes: impl FnMut(&Checkpoint),
+) -> Result<Frame, CheckpointError> {
+    let settings = CheckpointSettings::new(scene, profile);
+    checkpoint.check(&settings)?;
+    // The total samples may have changed, and should be saved from now on
+    checkpoint.settings = settings;
+    Ok(render_checkpoint(
+        scene,
+        profile,
+        checkpoint,
+        show_progress,
+        between_passes,
+    ))
+}
+
+fn render_checkpoint(
+    scene: &Scene,
+    profile: &Profile,
+    checkpoint: &mut Checkpoint,
+    show_progress: bool,
+    between_passes: impl FnMut(&Checkpoint),
+) -> Frame {
+    Camera::initialise(profile, &scene.camera, &scene.sky).render(
+        &scene.world,
+        checkpoint,
+        show_progress,
+        between_passes,
+    );
+    let mut frame = checkpoint.film.frame();
+    if profile.denoise {
+        frame.image = denoise(&frame);
+    }
+    frame
+}
@@ -2,7 +2,9 @@ use collar::CollectArray;
 use thiserror::Error;
 
 use crate::{
-    scene_reader::{ReadDictionary, get_texture, parse_bool, parse_f32},
+    scene_reader::{
+        ReadDictionary, ValueError, get_texture, parse_bool, parse_f32,
+    },
     textures::{material::Material, texture::Texture},
 };
 
@@ -16,6 +18,10 @@ pub enum MaterialError {
     Light(String),
     #[error("{0} is not a valid description for a glass")]
     Glass(String),
+    #[error("{0} is not a valid description for a pbr material")]
+    Pbr(String),
+    #[error(transparent)]
+    Value(#[from] ValueError),
 }
 
 type MaterialResult = Result<Material, MaterialError>;
@@ -36,10 +42,10 @@ pub(super) fn parse_full(
     else {
         return Err(MaterialError::Full(description.to_owned()));
     };
-    let smoothness = parse_f32(smoothness);
-    let texture = get_texture(texture_name, textures);
-    let refraction_chance = parse_bool(refraction_chance);
-    let refractive_index = parse_f32(refractive_index);
+    let smoothness = parse_f32(smoothness)?;
+    let texture = get_texture(texture_name, textures)?;
+    let refraction_chance = parse_bool(refraction_chance)?;
+    let refractive_index = parse_f32(refractive_index)?;
     let is_light = is_light == "true";
     Ok(Material::new(
         smoothness,
@@ -47,6 +53,8 @@ pub(super) fn parse_full(
         refraction_chance,
         refractive_index,
         is_light,
+        None,
+        0,
     ))
 }
 
@@ -59,8 +67,8 @@ pub(super) fn parse_opaque(
     else {
         return Err(MaterialError::Opaque(description.to_owned()));
     };
-    let smoothness = parse_f32(smoothness);
-    let texture = get_texture(texture_name, textures);
+    let smoothness = parse_f32(smoothness)?;
+    let texture = get_texture(texture_name, textures)?;
     Ok(Material::new_opaque(smoothness, texture))
 }
 
@@ -72,7 +80,7 @@ pub(super) fn parse_light(
     else {
         return Err(MaterialError::Light(description.to_owned()));
     };
-    let texture = get_texture(texture_name, textures);
+    let texture = get_texture(texture_name, textures)?;
     Ok(Material::new_light(texture))
 }
 
@@ -85,7 +93,22 @@ pub(super) fn parse_glass(
     else {
         return Err(MaterialError::Glass(description.to_owned()));
     };
-    let refractive_index = parse_f32(refractive_index);
-    let texture = get_texture(texture_name, textures);
+    let refractive_index = parse_f32(refractive_index)?;
+    let texture = get_texture(texture_name, textures)?;
     Ok(Material::new_glass(refractive_index, texture))
 }
+
+pub(super) fn parse_pbr(
+    description: &str,
+    textures: ReadDictionary<Texture>,
+) -> MaterialResult {
+    let Ok([roughness, metallic, texture_name]) =
+        description.split(',').collect_array_checked()
+    else {
+        return Err(MaterialError::Pbr(description.to_owned()));
+    };
+    let roughness = parse_f32(roughness)?;
+    let metallic = parse_f32(metallic)?;
+    let texture = get_texture(texture_name, textures)?;
+    Ok(Material::new_pbr(roughness, metallic, texture))
+}
@@ -1,58 +1,186 @@
-use std::collections::HashMap;
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use thiserror::Error;
 
 use crate::{
+    checkpoint::SceneHasher,
     colour::Colour,
+    geometry::Point3,
     textures::{material::Material, texture::Texture},
 };
 
+use object_parser::Group;
+
 mod material_parser;
+mod obj_parser;
 mod object_parser;
 mod row_parser;
-pub mod scene_parser;
+pub(crate) mod scene_parser;
 mod texture_parser;
 
 pub(super) type ReadDictionary<'a, T> = &'a HashMap<String, T>;
 type WriteDictionary<'a, T> = &'a mut HashMap<String, T>;
 
+/// Everything a scene has named so far, for later rows to refer back to
+#[derive(Debug, Default)]
+struct Names {
+    points: HashMap<String, Point3>,
+    colours: HashMap<String, Colour>,
+    textures: HashMap<String, Texture>,
+    materials: HashMap<String, Material>,
+    groups: HashMap<String, Group>,
+}
+
+/// Where a row's files are found, and the hash of the scene that each file
+/// read is added to. Paths are taken from the row as it was written,
+/// because the rest of it is lowercased and has its whitespace removed
+/// before parsing
+#[derive(Copy, Clone, Debug)]
+struct RowFiles<'a> {
+    directory: &'a Path,
+    row: &'a str,
+    hasher: &'a RefCell<SceneHasher>,
+}
+
+impl<'a> RowFiles<'a> {
+    fn new(
+        directory: &'a Path,
+        row: &'a str,
+        hasher: &'a RefCell<SceneHasher>,
+    ) -> Self {
+        Self {
+            directory,
+            row,
+            hasher,
+        }
+    }
+
+    /// The file named by the first field of the row's last section, relative
+    /// to the scene's directory
+    fn path(&self) -> PathBuf {
+        let section = self.row.rsplit(';').next().unwrap_or_default();
+        let path = section.split(',').next().unwrap_or_default().trim();
+        let path = self.directory.join(path);
+        self.hasher.borrow_mut().add_file(&path);
+        path
+    }
+
+    /// Adds the hash of a scene the row inherits from
+    fn inherit(&self, source_hash: Option<u64>) {
+        let source_hash = source_hash.unwrap_or_default();
+        self.hasher.borrow_mut().add(&source_hash.to_le_bytes());
+    }
+}
+
+/// A bad value inside a row, shared by all of the row parsers
+#[derive(Debug, Error)]
+pub enum ValueError {
+    #[error("{0:?} is an invalid f32")]
+    InvalidNumber(String),
+    #[error("{0:?} is an invalid boolean")]
+    InvalidBool(String),
+    #[error("{name:?} is not a known {kind}")]
+    UnknownName { kind: &'static str, name: String },
+    #[error("{name:?} is not a valid {kind}")]
+    UnknownKind { kind: &'static str, name: String },
+}
+
+impl ValueError {
+    /// The text in the row that caused the error
+    pub fn token(&self) -> &str {
+        match self {
+            Self::InvalidNumber(token) | Self::InvalidBool(token) => token,
+            Self::UnknownName { name, .. } | Self::UnknownKind { name, .. } => {
+                name
+            }
+        }
+    }
+
+    pub(super) fn unknown(kind: &'static str, name: &str) -> Self {
+        Self::UnknownName {
+            kind,
+            name: name.to_owned(),
+        }
+    }
+
+    pub(super) fn invalid(kind: &'static str, name: &str) -> Self {
+        Self::UnknownKind {
+            kind,
+            name: name.to_owned(),
+        }
+    }
+}
+
+type ValueResult<T> = Result<T, ValueError>;
+
+fn get_named<T: Clone>(
+    kind: &'static str,
+    name: &str,
+    dictionary: ReadDictionary<T>,
+) -> ValueResult<T> {
+    dictionary
+        .get(name)
+        .cloned()
+        .ok_or_else(|| ValueError::unknown(kind, name))
+}
+
+pub(super) fn get_point(
+    point_name: &str,
+    points: ReadDictionary<Point3>,
+) -> ValueResult<Point3> {
+    get_named("point name", point_name, points)
+}
+
 pub(super) fn get_colour(
     colour_name: &str,
     colours: ReadDictionary<Colour>,
-) -> Colour {
-    *colours
-        .get(colour_name)
-        .unwrap_or_else(|| panic!("{colour_name:?} is not a known colour name"))
+) -> ValueResult<Colour> {
+    get_named("colour name", colour_name, colours)
 }
 
 pub(super) fn get_texture(
     texture_name: &str,
     textures: ReadDictionary<Texture>,
-) -> Texture {
-    textures
-        .get(texture_name)
-        .unwrap_or_else(|| {
-            panic!("{texture_name:?} is not a known texture name")
-        })
-        .clone()
+) -> ValueResult<Texture> {
+    get_named("texture name", texture_name, textures)
 }
 
 pub(super) fn get_material(
     material_name: &str,
     materials: ReadDictionary<Material>,
-) -> Material {
-    materials
-        .get(material_name)
-        .unwrap_or_else(|| {
-            panic!("{material_name:?} is not a known texture name")
-        })
-        .clone()
+) -> ValueResult<Material> {
+    get_named("material name", material_name, materials)
+}
+
+/// The row with its whitespace removed and lowercased, which is how keywords
+/// and names are compared
+fn normalise(row: &str) -> String {
+    row.split_whitespace()
+        .collect::<String>()
+        .to_ascii_lowercase()
 }
 
-pub(super) fn parse_f32(num: &str) -> f32 {
+pub(super) fn parse_f32(num: &str) -> ValueResult<f32> {
     num.parse()
-        .unwrap_or_else(|_| panic!("{num:?} is an invalid f32"))
+        .map_err(|_| ValueError::InvalidNumber(num.to_owned()))
+}
+
+/// Parses every string in `nums`, failing on the first invalid one
+pub(super) fn parse_f32s<const N: usize>(
+    nums: [&str; N],
+) -> ValueResult<[f32; N]> {
+    let mut values = [0.; N];
+    for (value, num) in values.iter_mut().zip(nums) {
+        *value = parse_f32(num)?;
+    }
+    Ok(values)
 }
 
-pub(super) fn parse_bool(bool: &str) -> bool {
+pub(super) fn parse_bool(bool: &str) -> ValueResult<bool> {
     bool.parse()
-        .unwrap_or_else(|_| panic!("{bool:?} is an invalid boolean"))
+        .map_err(|_| ValueError::InvalidBool(bool.to_owned()))
 }
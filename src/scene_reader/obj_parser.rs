@@ -0,0 +1,361 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use glam::{Vec2, Vec3};
+use thiserror::Error;
+
+use crate::{
+    colour::Colour,
+    geometry::Point3,
+    hittables::mesh::{Mesh, MeshFace},
+    textures::material::Material,
+};
+
+#[derive(Debug, Error)]
+pub enum ObjError {
+    #[error("unable to read {path:?}: {source}")]
+    Io {
+        path: String,
+        source: std::io::Error,
+    },
+    #[error("line {line} of {path:?} is malformed: {row:?}")]
+    Malformed {
+        path: String,
+        line: usize,
+        row: String,
+    },
+    #[error("face on line {line} of {path:?} refers to a missing vertex")]
+    MissingVertex { path: String, line: usize },
+    #[error("face on line {line} of {path:?} has no material")]
+    MissingMaterial { path: String, line: usize },
+}
+
+type ObjResult<T> = Result<T, ObjError>;
+
+#[derive(Copy, Clone, Debug)]
+struct FaceVertex {
+    position: usize,
+    uv: Option<usize>,
+    normal: Option<usize>,
+}
+
+#[derive(Debug, Default)]
+struct ObjContents {
+    positions: Vec<Point3>,
+    uvs: Vec<Vec2>,
+    normals: Vec<Vec3>,
+    // Each face is a triangle, together with the material it was declared
+    // under
+    faces: Vec<([FaceVertex; 3], Option<String>, usize)>,
+    material_libraries: Vec<String>,
+}
+
+fn read_file(path: &Path) -> ObjResult<String> {
+    fs::read_to_string(path).map_err(|source| ObjError::Io {
+        path: path.display().to_string(),
+        source,
+    })
+}
+
+fn parse_floats<const N: usize>(
+    parts: &[&str],
+    path: &Path,
+    line: usize,
+    row: &str,
+) -> ObjResult<[f32; N]> {
+    let malformed = || ObjError::Malformed {
+        path: path.display().to_string(),
+        line,
+        row: row.to_owned(),
+    };
+    if parts.len() < N {
+        return Err(malformed());
+    }
+    let mut values = [0.; N];
+    for (value, part) in values.iter_mut().zip(parts) {
+        *value = part.parse().map_err(|_| malformed())?;
+    }
+    Ok(values)
+}
+
+/// Converts a one-based (or negative, relative) OBJ index into an offset
+fn resolve_index(index: &str, count: usize) -> Option<usize> {
+    let index = index.parse::<isize>().ok()?;
+    let resolved = match index {
+        0 => return None,
+        1.. => index - 1,
+        _ => count as isize + index,
+    };
+    usize::try_from(resolved).ok().filter(|&i| i < count)
+}
+
+fn parse_face_vertex(
+    token: &str,
+    contents: &ObjContents,
+) -> Option<FaceVertex> {
+    let mut indices = token.split('/');
+    let position = resolve_index(indices.next()?, contents.positions.len())?;
+    let uv = match indices.next() {
+        None | Some("") => None,
+        Some(index) => Some(resolve_index(index, contents.uvs.len())?),
+    };
+    let normal = match indices.next() {
+        None | Some("") => None,
+        Some(index) => Some(resolve_index(index, contents.normals.len())?),
+    };
+    Some(FaceVertex {
+        position,
+        uv,
+        normal,
+    })
+}
+
+fn parse_obj(path: &Path) -> ObjResult<ObjContents> {
+    let text = read_file(path)?;
+    let mut contents = ObjContents::default();
+    let mut current_material = None;
+    for (line_index, row) in text.lines().enumerate() {
+        let line = line_index + 1;
+        let row = row.split('#').next().unwrap_or_default();
+        let mut parts = row.split_whitespace();
+        let Some(keyword) = parts.next() else {
+            continue;
+        };
+        let parts = parts.collect::<Vec<_>>();
+        match keyword {
+            "v" => {
+                let [x, y, z] = parse_floats(&parts, path, line, row)?;
+                contents.positions.push(Point3::new(x, y, z));
+            }
+            "vt" => {
+                let [u] = parse_floats(&parts, path, line, row)?;
+                let v = parts.get(1).and_then(|v| v.parse().ok());
+                contents.uvs.push(Vec2::new(u, v.unwrap_or(0.)));
+            }
+            "vn" => {
+                let [x, y, z] = parse_floats(&parts, path, line, row)?;
+                contents.normals.push(Vec3::new(x, y, z));
+            }
+            "f" => {
+                let vertices = parts
+                    .iter()
+                    .map(|token| parse_face_vertex(token, &contents))
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or_else(|| ObjError::MissingVertex {
+                        path: path.display().to_string(),
+                        line,
+                    })?;
+                if vertices.len() < 3 {
+                    return Err(ObjError::Malformed {
+                        path: path.display().to_string(),
+                        line,
+                        row: row.to_owned(),
+                    });
+                }
+                // Fan triangulation, which is exact for convex polygons
+                for pair in vertices[1..].windows(2) {
+                    contents.faces.push((
+                        [vertices[0], pair[0], pair[1]],
+                        current_material.clone(),
+                        line,
+                    ));
+                }
+            }
+            "usemtl" => current_material = parts.first().map(|&s| s.into()),
+            "mtllib" => contents
+                .material_libraries
+                .extend(parts.iter().map(|&s| s.to_owned())),
+            _ => {}
+        }
+    }
+    Ok(contents)
+}
+
+/// The index of glass, for transparent materials which leave out `Ni`, as
+/// many exporters do. Defaulting to one would make them invisible.
+const DEFAULT_REFRACTIVE_INDEX: f32 = 1.5;
+
+#[derive(Debug, Default)]
+struct MtlEntry {
+    diffuse: Colour,
+    emissive: Colour,
+    specular_exponent: f32,
+    refractive_index: f32,
+    dissolve: f32,
+    illumination: u8,
+}
+
+impl MtlEntry {
+    fn to_material(&self) -> Material {
+        if self.emissive != Colour::BLACK {
+            Material::new_light(self.emissive.to_texture())
+        } else if self.dissolve < 1.
+            || matches!(self.illumination, 4 | 6 | 7 | 9)
+        {
+            Material::new_glass(
+                self.refractive_index,
+                self.diffuse.to_texture(),
+            )
+        } else {
+            // Map the Phong exponent (conventionally 0 to 1000) onto
+            // smoothness
+            let smoothness = (self.specular_exponent / 1000.).clamp(0., 1.);
+            Material::new_opaque(smoothness, self.diffuse.to_texture())
+        }
+    }
+}
+
+fn parse_mtl(path: &Path) -> ObjResult<HashMap<String, Material>> {
+    let text = read_file(path)?;
+    Ok(parse_mtl_entries(&text, path)?
+        .into_iter()
+        .map(|(name, entry)| (name, entry.to_material()))
+        .collect())
+}
+
+/// The entries of an `.mtl` file's `text`, in the order they are defined
+fn parse_mtl_entries(
+    text: &str,
+    path: &Path,
+) -> ObjResult<Vec<(String, MtlEntry)>> {
+    let mut entries = Vec::<(String, MtlEntry)>::new();
+    for (line_index, row) in text.lines().enumerate() {
+        let line = line_index + 1;
+        let row = row.split('#').next().unwrap_or_default();
+        let mut parts = row.split_whitespace();
+        let Some(keyword) = parts.next() else {
+            continue;
+        };
+        let parts = parts.collect::<Vec<_>>();
+        let keyword = keyword.to_ascii_lowercase();
+        if keyword == "newmtl" {
+            let name = parts.first().copied().unwrap_or_default().to_owned();
+            let entry = MtlEntry {
+                refractive_index: DEFAULT_REFRACTIVE_INDEX,
+                dissolve: 1.,
+                ..Default::default()
+            };
+            entries.push((name, entry));
+            continue;
+        }
+        let Some((_, entry)) = entries.last_mut() else {
+            continue;
+        };
+        match keyword.as_str() {
+            "kd" => {
+                let [r, g, b] = parse_floats(&parts, path, line, row)?;
+                entry.diffuse = Colour::new(r, g, b);
+            }
+            "ke" => {
+                let [r, g, b] = parse_floats(&parts, path, line, row)?;
+                entry.emissive = Colour::new(r, g, b);
+            }
+            "ns" => {
+                [entry.specular_exponent] =
+                    parse_floats(&parts, path, line, row)?
+            }
+            "ni" => {
+                [entry.refractive_index] =
+                    parse_floats(&parts, path, line, row)?
+            }
+            "d" => [entry.dissolve] = parse_floats(&parts, path, line, row)?,
+            "tr" => {
+                let [transparency] = parse_floats(&parts, path, line, row)?;
+                entry.dissolve = 1. - transparency;
+            }
+            "illum" => {
+                let [illumination] = parse_floats(&parts, path, line, row)?;
+                entry.illumination = illumination as u8;
+            }
+            _ => {}
+        }
+    }
+    Ok(entries)
+}
+
+/// Loads an OBJ file as a single mesh, using materials from its `.mtl`
+/// libraries where available and `fallback` otherwise
+pub(super) fn load_obj(
+    path: &Path,
+    fallback: Option<&Material>,
+) -> ObjResult<Mesh> {
+    let contents = parse_obj(path)?;
+    let directory = path.parent().unwrap_or(Path::new(""));
+    let mut library_materials = HashMap::new();
+    for library in &contents.material_libraries {
+        library_materials.extend(parse_mtl(&directory.join(library))?);
+    }
+
+    let mut materials = Vec::new();
+    let mut material_indices = HashMap::new();
+    let mut faces = Vec::with_capacity(contents.faces.len());
+    for (vertices, material_name, line) in &contents.faces {
+        let named = material_name
+            .as_ref()
+            .filter(|name| library_materials.contains_key(*name));
+        let material_index = match named {
+            Some(name) => *material_indices
+                .entry(Some(name.clone()))
+                .or_insert_with(|| {
+                    materials.push(library_materials[name].clone());
+                    materials.len() as u32 - 1
+                }),
+            None => {
+                let fallback =
+                    fallback.ok_or_else(|| ObjError::MissingMaterial {
+                        path: path.display().to_string(),
+                        line: *line,
+                    })?;
+                *material_indices.entry(None).or_insert_with(|| {
+                    materials.push(fallback.clone());
+                    materials.len() as u32 - 1
+                })
+            }
+        };
+
+        let all_indices =
+            |index: fn(&FaceVertex) -> Option<usize>| match vertices
+                .map(|vertex| index(&vertex))
+            {
+                [Some(a), Some(b), Some(c)] => {
+                    Some([a, b, c].map(|i| i as u32))
+                }
+                _ => None,
+            };
+        faces.push(MeshFace {
+            positions: vertices.map(|vertex| vertex.position as u32),
+            normals: all_indices(|vertex| vertex.normal),
+            uvs: all_indices(|vertex| vertex.uv),
+            material: material_index,
+        });
+    }
+
+    Ok(Mesh::new(
+        contents.positions,
+        contents.normals,
+        contents.uvs,
+        faces,
+        materials,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transparent_entries_without_ni_are_glass() {
+        let text = "newmtl window\n\
+                    Kd 0.9 0.9 1\n\
+                    d 0.2\n\
+                    newmtl water\n\
+                    Ni 1.33\n\
+                    illum 7\n";
+        let entries = parse_mtl_entries(text, Path::new("test.mtl")).unwrap();
+
+        let (name, window) = &entries[0];
+        assert_eq!(name, "window");
+        assert!(window.dissolve < 1.);
+        assert_eq!(window.refractive_index, 1.5);
+        assert_eq!(entries[1].1.refractive_index, 1.33);
+    }
+}
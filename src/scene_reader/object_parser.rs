@@ -1,13 +1,34 @@
+use std::sync::Arc;
+
+use collar::CollectArray;
+use glam::{Affine3A, Vec2, Vec3};
 use thiserror::Error;
 
 use crate::{
     geometry::Point3,
     hittables::{
-        hittable::HittableObject::{self},
+        capsule::Capsule,
+        cone::Cone,
+        cuboid::Cuboid,
+        cylinder::Cylinder,
+        disk::Disk,
+        hittable::{
+            HittableList,
+            HittableObject::{self},
+        },
+        instance::Instance,
+        plane::Plane,
+        quad::Quad,
         sphere::Sphere,
+        torus::Torus,
         triangle::Triangle,
     },
-    scene_reader::{ReadDictionary, get_material, parse_f32},
+    scene_reader::{
+        ReadDictionary, RowFiles, ValueError, WriteDictionary, get_material,
+        get_point,
+        obj_parser::{ObjError, load_obj},
+        parse_f32, parse_f32s,
+    },
     textures::material::Material,
 };
 
@@ -17,14 +38,169 @@ pub enum ObjectError {
     Sphere(String),
     #[error("{0} is not a valid description of a triangle")]
     Triangle(String),
+    #[error(
+        "{0} is not a valid description of vertex normals - expected three point names or nine numbers"
+    )]
+    VertexNormals(String),
+    #[error(
+        "{0} is not a valid description of vertex uvs - expected six numbers"
+    )]
+    VertexUvs(String),
+    #[error(
+        "{0} is not a valid triangle attribute - expected `normals=...` or `uvs=...`"
+    )]
+    TriangleAttribute(String),
+    #[error("{0} is not a valid description of a mesh")]
+    Mesh(String),
+    #[error(
+        "{0} is not a valid description of a plane - expected a point, a normal and a material"
+    )]
+    Plane(String),
+    #[error(
+        "{0} is not a valid description of a quad - expected a corner, two edges and a material"
+    )]
+    Quad(String),
+    #[error(
+        "{0} is not a valid description of a disk - expected a centre, a normal, a radius and a material"
+    )]
+    Disk(String),
+    #[error(
+        "{0} is not a valid description of a box - expected two opposite corners and a material"
+    )]
+    Box(String),
+    #[error(
+        "{0} is not a valid description of a cylinder - expected a base, a top, a radius, a material and optionally `uncapped`"
+    )]
+    Cylinder(String),
+    #[error(
+        "{0} is not a valid description of a cone - expected a base, an apex, a radius, a material and optionally `uncapped`"
+    )]
+    Cone(String),
+    #[error(
+        "{0} is not a valid description of a capsule - expected two end points, a radius and a material"
+    )]
+    Capsule(String),
+    #[error(
+        "{0} is not a valid description of a torus - expected a centre, an axis, the ring and tube radii and a material"
+    )]
+    Torus(String),
+    #[error(
+        "{0} is not a valid transform - expected `translate=x, y, z`, `rotate=x, y, z` in degrees, `scale=factor` or `scale=x, y, z`"
+    )]
+    Transform(String),
+    #[error("{0} flattens the group, so it can't be placed")]
+    Flattened(String),
+    #[error(transparent)]
+    Obj(#[from] ObjError),
+    #[error(transparent)]
+    Value(#[from] ValueError),
 }
 
 type ObjectResult = Result<HittableObject, ObjectError>;
 
-fn get_point(point_name: &str, points: ReadDictionary<Point3>) -> Point3 {
-    *points
-        .get(point_name)
-        .unwrap_or_else(|| panic!("{point_name:?} is not a known point name"))
+/// Objects gathered under a name by `group` rows, to be placed by
+/// `instance` rows
+#[derive(Debug, Default)]
+pub(super) struct Group {
+    objects: Vec<HittableObject>,
+    /// The hierarchy over the objects, which every instance shares until
+    /// more objects are added
+    built: Option<Arc<HittableList>>,
+}
+
+impl Group {
+    pub(super) fn extend(&mut self, objects: Vec<HittableObject>) {
+        self.objects.extend(objects);
+        self.built = None;
+    }
+
+    fn built(&mut self) -> Arc<HittableList> {
+        self.built
+            .get_or_insert_with(|| {
+                Arc::new(
+                    self.objects
+                        .iter()
+                        .cloned()
+                        .collect::<HittableList>()
+                        .optimise(),
+                )
+            })
+            .clone()
+    }
+}
+
+/// Reads the comma separated fields of an object's description in order,
+/// where points and vectors can be given by name or as three numbers
+struct Fields<'a> {
+    description: &'a str,
+    parts: std::str::Split<'a, char>,
+    invalid: fn(String) -> ObjectError,
+}
+
+impl<'a> Fields<'a> {
+    /// Fields of `description`, failing with `invalid` if there are too
+    /// few or too many
+    fn new(description: &'a str, invalid: fn(String) -> ObjectError) -> Self {
+        Self {
+            description,
+            parts: description.split(','),
+            invalid,
+        }
+    }
+
+    /// Reports `description` as the invalid text instead, for fields that
+    /// are only part of it
+    fn reporting(mut self, description: &'a str) -> Self {
+        self.description = description;
+        self
+    }
+
+    fn error(&self) -> ObjectError {
+        (self.invalid)(self.description.to_owned())
+    }
+
+    fn next(&mut self) -> Result<&'a str, ObjectError> {
+        self.parts.next().ok_or_else(|| self.error())
+    }
+
+    fn point(
+        &mut self,
+        points: ReadDictionary<Point3>,
+    ) -> Result<Point3, ObjectError> {
+        let first = self.next()?;
+        if first.parse::<f32>().is_err() {
+            return Ok(get_point(first, points)?);
+        }
+        let [x, y, z] = parse_f32s([first, self.next()?, self.next()?])?;
+        Ok(Point3::new(x, y, z))
+    }
+
+    fn number(&mut self) -> Result<f32, ObjectError> {
+        Ok(parse_f32(self.next()?)?)
+    }
+
+    fn material(
+        &mut self,
+        materials: ReadDictionary<Material>,
+    ) -> Result<Material, ObjectError> {
+        Ok(get_material(self.next()?, materials)?)
+    }
+
+    /// Whether the optional last field `word` is there
+    fn flag(&mut self, word: &str) -> Result<bool, ObjectError> {
+        match self.parts.next() {
+            Some(part) if part == word => Ok(true),
+            Some(_) => Err(self.error()),
+            None => Ok(false),
+        }
+    }
+
+    fn finish(mut self) -> Result<(), ObjectError> {
+        match self.parts.next() {
+            Some(_) => Err(self.error()),
+            None => Ok(()),
+        }
+    }
 }
 
 pub(super) fn parse_sphere(
@@ -38,21 +214,21 @@ pub(super) fn parse_sphere(
         3 => {
             let [point_name, radius, material_name] =
                 description_parts.try_into().unwrap();
-            let center = get_point(point_name, points);
-            let radius = parse_f32(radius);
-            let material = get_material(material_name, materials);
+            let center = get_point(point_name, points)?;
+            let radius = parse_f32(radius)?;
+            let material = get_material(material_name, materials)?;
             Ok((center, radius, material))
         }
         5 => {
             let [x, y, z, radius, material_name] =
                 description_parts.try_into().unwrap();
-            let [x, y, z] = [x, y, z].map(parse_f32);
+            let [x, y, z] = parse_f32s([x, y, z])?;
             let center = Point3::new(x, y, z);
-            let radius = parse_f32(radius);
-            let material = get_material(material_name, materials);
+            let radius = parse_f32(radius)?;
+            let material = get_material(material_name, materials)?;
             Ok((center, radius, material))
         }
-        _ => Err(ObjectError::Sphere(description.to_owned())), //panic!("{description:?} is not a valid description of a sphere"),
+        _ => Err(ObjectError::Sphere(description.to_owned())),
     }?;
     Ok(Sphere::new(center, radius, material).into())
 }
@@ -62,7 +238,9 @@ pub(super) fn parse_triangle(
     materials: ReadDictionary<Material>,
     points: ReadDictionary<Point3>,
 ) -> ObjectResult {
-    let description = description.replace(['(', ')'], "");
+    let mut sections = description.split(';');
+    let description =
+        sections.next().unwrap_or_default().replace(['(', ')'], "");
     let description_parts = description.split(',').collect::<Vec<_>>();
     let (corner_one, corner_two, corner_three, material) =
         match description_parts.len() {
@@ -73,10 +251,10 @@ pub(super) fn parse_triangle(
                     corner_three_name,
                     material_name,
                 ] = description_parts.try_into().unwrap();
-                let [corner_one, corner_two, corner_three] =
-                    [corner_one_name, corner_two_name, corner_three_name]
-                        .map(|name| get_point(name, points));
-                let material = get_material(material_name, materials);
+                let corner_one = get_point(corner_one_name, points)?;
+                let corner_two = get_point(corner_two_name, points)?;
+                let corner_three = get_point(corner_three_name, points)?;
+                let material = get_material(material_name, materials)?;
                 Ok((corner_one, corner_two, corner_three, material))
             }
             10 => {
@@ -102,12 +280,11 @@ pub(super) fn parse_triangle(
                     x_three,
                     y_three,
                     z_three,
-                ] = [
+                ] = parse_f32s([
                     x_one, y_one, z_one, x_two, y_two, z_two, x_three, y_three,
                     z_three,
-                ]
-                .map(parse_f32);
-                let material = get_material(material_name, materials);
+                ])?;
+                let material = get_material(material_name, materials)?;
                 let (corner_one, corner_two, corner_three) = (
                     Point3::new(x_one, y_one, z_one),
                     Point3::new(x_two, y_two, z_two),
@@ -117,5 +294,243 @@ pub(super) fn parse_triangle(
             }
             _ => Err(ObjectError::Triangle(description.to_owned())),
         }?;
-    Ok(Triangle::new(corner_one, corner_two, corner_three, material).into())
+    let mut triangle =
+        Triangle::new(corner_one, corner_two, corner_three, material);
+    for section in sections {
+        triangle = match section.split_once('=') {
+            Some(("normals", normals)) => triangle
+                .with_vertex_normals(parse_vertex_normals(normals, points)?),
+            Some(("uvs", uvs)) => {
+                triangle.with_vertex_uvs(parse_vertex_uvs(uvs)?)
+            }
+            _ => {
+                return Err(ObjectError::TriangleAttribute(section.to_owned()));
+            }
+        };
+    }
+    Ok(triangle.into())
+}
+
+fn parse_vertex_normals(
+    description: &str,
+    points: ReadDictionary<Point3>,
+) -> Result<[Vec3; 3], ObjectError> {
+    let description = description.replace(['(', ')'], "");
+    let description_parts = description.split(',').collect::<Vec<_>>();
+    match description_parts[..] {
+        [one, two, three] => Ok([
+            *get_point(one, points)?,
+            *get_point(two, points)?,
+            *get_point(three, points)?,
+        ]),
+        [x1, y1, z1, x2, y2, z2, x3, y3, z3] => {
+            let [x1, y1, z1, x2, y2, z2, x3, y3, z3] =
+                parse_f32s([x1, y1, z1, x2, y2, z2, x3, y3, z3])?;
+            Ok([
+                Vec3::new(x1, y1, z1),
+                Vec3::new(x2, y2, z2),
+                Vec3::new(x3, y3, z3),
+            ])
+        }
+        _ => Err(ObjectError::VertexNormals(description.to_owned())),
+    }
+}
+
+fn parse_vertex_uvs(description: &str) -> Result<[Vec2; 3], ObjectError> {
+    let description = description.replace(['(', ')'], "");
+    let Ok(parts) = description.split(',').collect_array_checked() else {
+        return Err(ObjectError::VertexUvs(description.to_owned()));
+    };
+    let [u1, v1, u2, v2, u3, v3] = parse_f32s(parts)?;
+    Ok([Vec2::new(u1, v1), Vec2::new(u2, v2), Vec2::new(u3, v3)])
+}
+
+pub(super) fn parse_mesh(
+    description: &str,
+    materials: ReadDictionary<Material>,
+    files: RowFiles,
+) -> ObjectResult {
+    let description_parts = description.split(',').collect::<Vec<_>>();
+    let fallback = match description_parts[..] {
+        [_] => None,
+        [_, material_name] => Some(get_material(material_name, materials)?),
+        _ => return Err(ObjectError::Mesh(description.to_owned())),
+    };
+    Ok(load_obj(&files.path(), fallback.as_ref())?.into())
+}
+
+pub(super) fn parse_plane(
+    description: &str,
+    materials: ReadDictionary<Material>,
+    points: ReadDictionary<Point3>,
+) -> ObjectResult {
+    let description = description.replace(['(', ')'], "");
+    let mut fields = Fields::new(&description, ObjectError::Plane);
+    let point = fields.point(points)?;
+    let normal = *fields.point(points)?;
+    let material = fields.material(materials)?;
+    fields.finish()?;
+    Ok(Plane::new(point, normal, material).into())
+}
+
+pub(super) fn parse_quad(
+    description: &str,
+    materials: ReadDictionary<Material>,
+    points: ReadDictionary<Point3>,
+) -> ObjectResult {
+    let description = description.replace(['(', ')'], "");
+    let mut fields = Fields::new(&description, ObjectError::Quad);
+    let corner = fields.point(points)?;
+    let edge_u = *fields.point(points)?;
+    let edge_v = *fields.point(points)?;
+    let material = fields.material(materials)?;
+    fields.finish()?;
+    Ok(Quad::new(corner, edge_u, edge_v, material).into())
+}
+
+pub(super) fn parse_disk(
+    description: &str,
+    materials: ReadDictionary<Material>,
+    points: ReadDictionary<Point3>,
+) -> ObjectResult {
+    let description = description.replace(['(', ')'], "");
+    let mut fields = Fields::new(&description, ObjectError::Disk);
+    let centre = fields.point(points)?;
+    let normal = *fields.point(points)?;
+    let radius = fields.number()?;
+    let material = fields.material(materials)?;
+    fields.finish()?;
+    Ok(Disk::new(centre, normal, radius, material).into())
+}
+
+pub(super) fn parse_box(
+    description: &str,
+    materials: ReadDictionary<Material>,
+    points: ReadDictionary<Point3>,
+) -> ObjectResult {
+    let description = description.replace(['(', ')'], "");
+    let mut fields = Fields::new(&description, ObjectError::Box);
+    let corner = fields.point(points)?;
+    let opposite_corner = fields.point(points)?;
+    let material = fields.material(materials)?;
+    fields.finish()?;
+    Ok(Cuboid::new(corner, opposite_corner, material).into())
+}
+
+pub(super) fn parse_cylinder(
+    description: &str,
+    materials: ReadDictionary<Material>,
+    points: ReadDictionary<Point3>,
+) -> ObjectResult {
+    let description = description.replace(['(', ')'], "");
+    let mut fields = Fields::new(&description, ObjectError::Cylinder);
+    let base = fields.point(points)?;
+    let top = fields.point(points)?;
+    let radius = fields.number()?;
+    let material = fields.material(materials)?;
+    let uncapped = fields.flag("uncapped")?;
+    fields.finish()?;
+    Ok(Cylinder::new(base, top, radius, !uncapped, material).into())
+}
+
+pub(super) fn parse_cone(
+    description: &str,
+    materials: ReadDictionary<Material>,
+    points: ReadDictionary<Point3>,
+) -> ObjectResult {
+    let description = description.replace(['(', ')'], "");
+    let mut fields = Fields::new(&description, ObjectError::Cone);
+    let base = fields.point(points)?;
+    let apex = fields.point(points)?;
+    let radius = fields.number()?;
+    let material = fields.material(materials)?;
+    let uncapped = fields.flag("uncapped")?;
+    fields.finish()?;
+    Ok(Cone::new(base, apex, radius, !uncapped, material).into())
+}
+
+pub(super) fn parse_capsule(
+    description: &str,
+    materials: ReadDictionary<Material>,
+    points: ReadDictionary<Point3>,
+) -> ObjectResult {
+    let description = description.replace(['(', ')'], "");
+    let mut fields = Fields::new(&description, ObjectError::Capsule);
+    let start = fields.point(points)?;
+    let end = fields.point(points)?;
+    let radius = fields.number()?;
+    let material = fields.material(materials)?;
+    fields.finish()?;
+    Ok(Capsule::new(start, end, radius, material).into())
+}
+
+pub(super) fn parse_torus(
+    description: &str,
+    materials: ReadDictionary<Material>,
+    points: ReadDictionary<Point3>,
+) -> ObjectResult {
+    let description = description.replace(['(', ')'], "");
+    let mut fields = Fields::new(&description, ObjectError::Torus);
+    let centre = fields.point(points)?;
+    let axis = *fields.point(points)?;
+    let major_radius = fields.number()?;
+    let minor_radius = fields.number()?;
+    let material = fields.material(materials)?;
+    fields.finish()?;
+    Ok(Torus::new(centre, axis, major_radius, minor_radius, material).into())
+}
+
+pub(super) fn parse_instance(
+    description: &str,
+    groups: WriteDictionary<Group>,
+    points: ReadDictionary<Point3>,
+) -> ObjectResult {
+    let mut sections = description.split(';');
+    let name = sections.next().unwrap_or_default();
+    let name = name.strip_prefix("name=").unwrap_or(name);
+    // Each transform applies after the ones written before it
+    let mut transform = Affine3A::IDENTITY;
+    for section in sections {
+        transform = parse_transform(section, points)? * transform;
+    }
+    // Small scales are fine, so only a transform that can't be undone is
+    // rejected
+    if transform.matrix3.determinant() == 0. || !transform.inverse().is_finite()
+    {
+        return Err(ObjectError::Flattened(description.to_owned()));
+    }
+    let group = groups
+        .get_mut(name)
+        .ok_or_else(|| ValueError::unknown("group name", name))?;
+    Ok(Instance::new(group.built(), transform).into())
+}
+
+fn parse_transform(
+    section: &str,
+    points: ReadDictionary<Point3>,
+) -> Result<Affine3A, ObjectError> {
+    let invalid = || ObjectError::Transform(section.to_owned());
+    let (kind, value) = section.split_once('=').ok_or_else(invalid)?;
+    let value = value.replace(['(', ')'], "");
+    let vector = || {
+        let mut fields =
+            Fields::new(&value, ObjectError::Transform).reporting(section);
+        let vector = *fields.point(points)?;
+        fields.finish()?;
+        Ok::<_, ObjectError>(vector)
+    };
+    match kind {
+        "translate" => Ok(Affine3A::from_translation(vector()?)),
+        "rotate" => {
+            let [x, y, z] = vector()?.to_array().map(f32::to_radians);
+            Ok(Affine3A::from_rotation_z(z)
+                * Affine3A::from_rotation_y(y)
+                * Affine3A::from_rotation_x(x))
+        }
+        "scale" => match parse_f32(&value) {
+            Ok(factor) => Ok(Affine3A::from_scale(Vec3::splat(factor))),
+            Err(_) => Ok(Affine3A::from_scale(vector()?)),
+        },
+        _ => Err(invalid()),
+    }
 }
@@ -1,26 +1,91 @@
 use collar::CollectArray;
+use thiserror::Error;
 
 use crate::{
     colour::Colour,
     geometry::Point3,
     hittables::hittable::HittableObject,
     scene_reader::{
-        ReadDictionary, WriteDictionary,
-        material_parser::{parse_full, parse_glass, parse_light, parse_opaque},
-        object_parser::{parse_sphere, parse_triangle},
-        parse_f32,
-        scene_parser::read_scene,
+        Names, ReadDictionary, RowFiles, ValueError, WriteDictionary,
+        material_parser::{
+            MaterialError, parse_full, parse_glass, parse_light, parse_opaque,
+            parse_pbr,
+        },
+        normalise,
+        object_parser::{
+            Group, ObjectError, parse_box, parse_capsule, parse_cone,
+            parse_cylinder, parse_disk, parse_instance, parse_mesh,
+            parse_plane, parse_quad, parse_sphere, parse_torus, parse_triangle,
+        },
+        parse_f32, parse_f32s,
+        scene_parser::{SceneErrors, read_scene},
         texture_parser::{
-            parse_checkerboard, parse_gradient, parse_perlin, parse_solid,
-            parse_stripe,
+            TextureError, parse_checkerboard, parse_gradient, parse_image,
+            parse_perlin, parse_solid, parse_stripe,
         },
     },
     textures::{material::Material, texture::Texture},
+    tone_mapping::{ToneMapper, ToneMapping},
 };
 
+#[derive(Debug, Error)]
+pub enum RowError {
+    #[error(
+        "{0:?} is not a valid description for the camera - expected (from_x, from_y, from_z), (at_x, at_y, at_z), camera_tilt, fov, aspect_ratio, focus_distance, defocus_angle"
+    )]
+    Camera(String),
+    #[error("camera data not given")]
+    MissingCamera,
+    #[error(
+        "{0:?} is not a valid description for the sky colour - expected (r1, g1, b1), (r2, g2, b2)"
+    )]
+    Sky(String),
+    #[error("sky colour data not given")]
+    MissingSky,
+    #[error("row type not properly delimited")]
+    RowType,
+    #[error("name not provided")]
+    Name,
+    #[error("{0} type not properly delimited")]
+    Kind(&'static str),
+    #[error("expected three parameters for a point, got {0:?}")]
+    Point(String),
+    #[error("expected three parameters for a colour, got {0:?}")]
+    Colour(String),
+    #[error(
+        "{0:?} is not a valid tone mapping - expected operator, exposure[, white_point]"
+    )]
+    ToneMapping(String),
+    #[error(transparent)]
+    Texture(#[from] TextureError),
+    #[error(transparent)]
+    Material(#[from] MaterialError),
+    #[error(transparent)]
+    Object(#[from] ObjectError),
+    #[error(transparent)]
+    Value(#[from] ValueError),
+    #[error("the inherited scene has errors:\n{0}")]
+    Inherit(SceneErrors),
+}
+
+impl RowError {
+    /// The bad value behind the error, if there is one
+    pub fn value_error(&self) -> Option<&ValueError> {
+        match self {
+            Self::Value(error)
+            | Self::Texture(TextureError::Value(error))
+            | Self::Material(MaterialError::Value(error))
+            | Self::Object(ObjectError::Value(error)) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+type RowResult<T> = Result<T, RowError>;
+
 pub(super) fn parse_camera_data(
     description: &str,
-) -> (Point3, Point3, f32, f32, f32, f32, f32) {
+) -> RowResult<(Point3, Point3, f32, f32, f32, f32, f32)> {
     let description = description.replace(['(', ')'], "");
     let Ok(
         [
@@ -38,10 +103,7 @@ pub(super) fn parse_camera_data(
         ],
     ) = description.split(',').collect_array_checked()
     else {
-        panic!(
-            "{description:?} is not a valid description for the camera; 
-        expected (from_x, from_y, from_z), (at_x, at_y, at_z), camera_tilt fov, aspect_ratio, focus_distance, defocus_angle"
-        )
+        return Err(RowError::Camera(description));
     };
     let [
         from_x,
@@ -55,7 +117,7 @@ pub(super) fn parse_camera_data(
         aspect_ratio,
         focus_distance,
         defocus_angle,
-    ] = [
+    ] = parse_f32s([
         from_x,
         from_y,
         from_z,
@@ -67,9 +129,8 @@ pub(super) fn parse_camera_data(
         aspect_ratio,
         focus_distance,
         defocus_angle,
-    ]
-    .map(parse_f32);
-    (
+    ])?;
+    Ok((
         Point3::new(from_x, from_y, from_z),
         Point3::new(at_x, at_y, at_z),
         camera_tilt,
@@ -77,93 +138,152 @@ pub(super) fn parse_camera_data(
         aspect_ratio,
         focus_distance,
         defocus_angle,
-    )
+    ))
 }
 
-pub(super) fn parse_sky_colour(description: &str) -> (Colour, Colour) {
+pub(super) fn parse_sky_colour(
+    description: &str,
+) -> RowResult<(Colour, Colour)> {
     let description = description.replace(['(', ')'], "");
-    let Ok([r1, g1, b1, r2, g2, b2]) =
-        description.split(',').collect_array_checked()
-    else {
-        panic!(
-            "{description:?} is not a valid description for the sky colour; 
-        expected (r1, g1, b1), (r2, g2, b2)"
-        )
+    let Ok(parts) = description.split(',').collect_array_checked() else {
+        return Err(RowError::Sky(description));
     };
-    let [r1, g1, b1, r2, g2, b2] = [r1, g1, b1, r2, g2, b2].map(parse_f32);
-    (Colour::new(r1, g1, b1), Colour::new(r2, g2, b2))
+    let [r1, g1, b1, r2, g2, b2] = parse_f32s(parts)?;
+    Ok((Colour::new(r1, g1, b1), Colour::new(r2, g2, b2)))
 }
 
 pub(super) fn parse_row(
     row: &str,
-    points: WriteDictionary<Point3>,
-    colours: WriteDictionary<Colour>,
-    textures: WriteDictionary<Texture>,
-    materials: WriteDictionary<Material>,
-) -> Option<Vec<HittableObject>> {
-    let row = row.split_whitespace().collect::<String>();
+    names: &mut Names,
+    tone_mapping: &mut Option<ToneMapping>,
+    files: RowFiles,
+) -> RowResult<Option<Vec<HittableObject>>> {
+    let Names {
+        points,
+        colours,
+        textures,
+        materials,
+        groups,
+    } = names;
+    let row = normalise(row);
     if row.is_empty() || row.starts_with("//") {
-        return None;
+        return Ok(None);
     }
-    let (row_type, row_data) = row
-        .split_once(';')
-        .unwrap_or_else(|| panic!("{row:?} - row type not properly delimited"));
-    if row_type == "object" {
-        return Some(parse_object(row_data, materials, points));
+    let (row_type, row_data) = row.split_once(';').ok_or(RowError::RowType)?;
+    if let Some(objects) =
+        parse_objects(row_type, row_data, materials, points, groups, files)?
+    {
+        return Ok(Some(objects));
     }
-    if row_type == "inherit" {
-        return Some(parse_scene(row_data));
+    if row_type == "group" {
+        let (name, row) = row_data.split_once(';').ok_or(RowError::Name)?;
+        let name = name.strip_prefix("name=").unwrap_or(name);
+        let (row_type, row_data) =
+            row.split_once(';').ok_or(RowError::RowType)?;
+        let objects = parse_objects(
+            row_type, row_data, materials, points, groups, files,
+        )?
+        .ok_or_else(|| ValueError::invalid("group row type", row_type))?;
+        groups.entry(name.to_owned()).or_default().extend(objects);
+        return Ok(None);
     }
-    let (name, description) = row_data
-        .split_once(';')
-        .unwrap_or_else(|| panic!("Name not provided for row {row}"));
+    if row_type == "tonemap" {
+        *tone_mapping = Some(parse_tone_mapping(row_data)?);
+        return Ok(None);
+    }
+    let (name, description) = row_data.split_once(';').ok_or(RowError::Name)?;
     let name = name.strip_prefix("name=").unwrap_or(name).to_owned();
     match row_type {
         "point" => parse_point(name, description, points),
         "colour" => parse_colour(name, description, colours),
-        "texture" => parse_texture(name, description, textures, colours),
+        "texture" => parse_texture(name, description, textures, colours, files),
         "material" => parse_material(name, description, materials, textures),
-        _ => panic!("{row_type:?} is not a valid row type"),
+        _ => Err(ValueError::invalid("row type", row_type).into()),
+    }?;
+    Ok(None)
+}
+
+/// The objects made by an `object`, `inherit` or `instance` row, which can
+/// also be gathered into a group, or `None` for any other row
+fn parse_objects(
+    row_type: &str,
+    row_data: &str,
+    materials: WriteDictionary<Material>,
+    points: ReadDictionary<Point3>,
+    groups: WriteDictionary<Group>,
+    files: RowFiles,
+) -> RowResult<Option<Vec<HittableObject>>> {
+    match row_type {
+        "object" => parse_object(row_data, materials, points, files),
+        "inherit" => parse_scene(row_data, files),
+        "instance" => Ok(vec![parse_instance(row_data, groups, points)?]),
+        _ => return Ok(None),
     }
-    None
+    .map(Some)
+}
+
+fn parse_scene(
+    scene_name: &str,
+    files: RowFiles,
+) -> RowResult<Vec<HittableObject>> {
+    let file_name = format!("{}.scene", scene_name.to_ascii_lowercase());
+    read_scene(&files.directory.join(file_name))
+        .map(|scene| {
+            files.inherit(scene.source_hash);
+            scene.world.into_objects()
+        })
+        .map_err(RowError::Inherit)
 }
 
-fn parse_scene(scene_name: &str) -> Vec<HittableObject> {
-    read_scene(format!("scenes/{}.scene", scene_name.to_ascii_lowercase()))
-        .0
-        .data
+fn parse_tone_mapping(description: &str) -> RowResult<ToneMapping> {
+    let description = description.strip_suffix(';').unwrap_or(description);
+    let (operator, exposure, white_point) =
+        match description.split(',').collect::<Vec<_>>()[..] {
+            [operator, exposure] => (operator, exposure, None),
+            [operator, exposure, white_point] => {
+                (operator, exposure, Some(white_point))
+            }
+            _ => return Err(RowError::ToneMapping(description.to_owned())),
+        };
+    let operator = operator.strip_prefix("type=").unwrap_or(operator);
+    let operator = ToneMapper::from_name(operator).ok_or_else(|| {
+        ValueError::invalid("tone mapping operator", operator)
+    })?;
+    let white_point = match white_point {
+        Some(white_point) => parse_f32(white_point)?,
+        None => ToneMapping::default().white_point,
+    };
+    Ok(ToneMapping {
+        operator,
+        exposure: parse_f32(exposure)?,
+        white_point,
+    })
 }
 
 fn parse_point(
     name: String,
     description: &str,
     points: WriteDictionary<Point3>,
-) {
-    let [x, y, z] = description
-        .split(',')
-        .collect::<Vec<_>>()
-        .try_into()
-        .unwrap_or_else(|_| {
-            panic!("Expected three parameters for colour, got {description:?}")
-        });
-    let point = Point3::new(parse_f32(x), parse_f32(y), parse_f32(z));
-    points.insert(name, point);
+) -> RowResult<()> {
+    let Ok(parts) = description.split(',').collect_array_checked() else {
+        return Err(RowError::Point(description.to_owned()));
+    };
+    let [x, y, z] = parse_f32s(parts)?;
+    points.insert(name, Point3::new(x, y, z));
+    Ok(())
 }
 
 fn parse_colour(
     name: String,
     description: &str,
     colours: WriteDictionary<Colour>,
-) {
-    let [red, green, blue] = description
-        .split(',')
-        .collect::<Vec<_>>()
-        .try_into()
-        .unwrap_or_else(|_| {
-            panic!("Expected three parameters for colour, got {description:?}")
-        });
-    let colour = Colour::new(parse_f32(red), parse_f32(green), parse_f32(blue));
-    colours.insert(name, colour);
+) -> RowResult<()> {
+    let Ok(parts) = description.split(',').collect_array_checked() else {
+        return Err(RowError::Colour(description.to_owned()));
+    };
+    let [red, green, blue] = parse_f32s(parts)?;
+    colours.insert(name, Colour::new(red, green, blue));
+    Ok(())
 }
 
 fn parse_texture(
@@ -171,11 +291,11 @@ fn parse_texture(
     description: &str,
     textures: WriteDictionary<Texture>,
     colours: ReadDictionary<Colour>,
-) {
-    let (texture_type, description) =
-        description.split_once(';').unwrap_or_else(|| {
-            panic!("Type of texture not properly delimited for {description}")
-        });
+    files: RowFiles,
+) -> RowResult<()> {
+    let (texture_type, description) = description
+        .split_once(';')
+        .ok_or(RowError::Kind("texture"))?;
     let texture_type =
         texture_type.strip_prefix("type=").unwrap_or(texture_type);
     let texture = match texture_type {
@@ -184,11 +304,12 @@ fn parse_texture(
         "checker" => parse_checkerboard(description, textures),
         "stripe" => parse_stripe(description, textures),
         "gradient" => parse_gradient(description, textures),
+        "image" => parse_image(description, files),
         "uv" => Ok(Texture::UV),
-        _ => panic!("{texture_type:?} is not a valid texture"),
-    }
-    .unwrap();
+        _ => Err(ValueError::invalid("texture type", texture_type).into()),
+    }?;
     textures.insert(name, texture);
+    Ok(())
 }
 
 fn parse_material(
@@ -196,38 +317,50 @@ fn parse_material(
     description: &str,
     materials: WriteDictionary<Material>,
     textures: ReadDictionary<Texture>,
-) {
-    let (mode, description) =
-        description.split_once(';').unwrap_or_else(|| {
-            panic!("Material mode not provided for {description}")
-        });
+) -> RowResult<()> {
+    let (mode, description) = description
+        .split_once(';')
+        .ok_or(RowError::Kind("material"))?;
     let mode = mode.strip_prefix("type=").unwrap_or(mode);
     let material = match mode {
         "full" => parse_full(description, textures),
         "opaque" => parse_opaque(description, textures),
         "light" => parse_light(description, textures),
         "glass" => parse_glass(description, textures),
-        _ => panic!("{mode:?} is an invalid mode"),
-    }
-    .unwrap();
-    materials.insert(name, material);
+        "pbr" => parse_pbr(description, textures),
+        _ => Err(ValueError::invalid("material mode", mode).into()),
+    }?;
+    // Redefining a material keeps its number
+    let id = materials
+        .get(&name)
+        .map_or(materials.len() as u32 + 1, |material| material.id);
+    materials.insert(name, material.with_id(id));
+    Ok(())
 }
 
 fn parse_object(
     description: &str,
     materials: WriteDictionary<Material>,
     points: ReadDictionary<Point3>,
-) -> Vec<HittableObject> {
+    files: RowFiles,
+) -> RowResult<Vec<HittableObject>> {
     let (object_type, description) = description
         .split_once(';')
-        .unwrap_or_else(|| panic!("Object type not given for {description}"));
+        .ok_or(RowError::Kind("object"))?;
     let object_type = object_type.strip_prefix("type=").unwrap_or(object_type);
-    vec![
-        match object_type {
-            "sphere" => parse_sphere(description, materials, points),
-            "triangle" => parse_triangle(description, materials, points),
-            _ => panic!("{object_type:?} is not a valid object"),
-        }
-        .unwrap(),
-    ]
+    let object = match object_type {
+        "sphere" => parse_sphere(description, materials, points),
+        "triangle" => parse_triangle(description, materials, points),
+        "mesh" => parse_mesh(description, materials, files),
+        "plane" => parse_plane(description, materials, points),
+        "quad" => parse_quad(description, materials, points),
+        "disk" => parse_disk(description, materials, points),
+        "box" => parse_box(description, materials, points),
+        "cylinder" => parse_cylinder(description, materials, points),
+        "cone" => parse_cone(description, materials, points),
+        "capsule" => parse_capsule(description, materials, points),
+        "torus" => parse_torus(description, materials, points),
+        _ => Err(ValueError::invalid("object type", object_type).into()),
+    }?;
+    Ok(vec![object])
 }
@@ -1,73 +1,191 @@
-use std::{collections::HashMap, fs::File, io::Read};
+use std::{cell::RefCell, fmt, fs, path::Path};
+
+use thiserror::Error;
 
 use crate::{
-    camera::CameraInfo,
+    checkpoint::SceneHasher,
     hittables::hittable::HittableList,
-    scene_reader::row_parser::{
-        parse_camera_data, parse_row, parse_sky_colour,
+    scene::{CameraSettings, Scene, Sky},
+    scene_reader::{
+        Names, RowFiles, ValueError, normalise,
+        row_parser::{
+            RowError, parse_camera_data, parse_row, parse_sky_colour,
+        },
     },
 };
 
-pub fn read_scene(path: String) -> (HittableList, CameraInfo) {
-    let mut file = File::open(path).expect("Unable to open the file");
-    let mut contents = String::new();
-    file.read_to_string(&mut contents)
-        .expect("Contents unreadable");
-
-    let mut points = HashMap::new();
-    let mut textures = HashMap::new();
-    let mut materials = HashMap::new();
-    let mut colours = HashMap::new();
-    let lowered = contents.to_ascii_lowercase();
-    let mut lines = lowered.lines();
-
-    let first_line = lines
-        .next()
-        .expect("camera data not given")
-        .split_whitespace()
-        .collect::<String>();
+#[derive(Debug, Error)]
+pub enum SceneError {
+    #[error("unable to read {path:?}: {source}")]
+    Io {
+        path: String,
+        source: std::io::Error,
+    },
+    #[error("{path}:{line}{}: {source}\n    {row}", format_column(*.column))]
+    Row {
+        path: String,
+        line: usize,
+        /// Where the offending value starts in the row, when it can be found
+        /// (taking the last match, since values follow the row's own name)
+        column: Option<usize>,
+        row: String,
+        source: RowError,
+    },
+}
+
+fn format_column(column: Option<usize>) -> String {
+    column
+        .map(|column| format!(":{column}"))
+        .unwrap_or_default()
+}
+
+impl SceneError {
+    fn row(path: &str, line: usize, row: &str, source: RowError) -> Self {
+        let column = source.value_error().and_then(|error| {
+            let lowered = row.to_ascii_lowercase();
+            lowered.rfind(error.token()).map(|index| index + 1)
+        });
+        Self::Row {
+            path: path.to_owned(),
+            line,
+            column,
+            row: row.trim().to_owned(),
+            source,
+        }
+    }
+
+    /// The name that failed to resolve, if that is what went wrong
+    pub fn unknown_name(&self) -> Option<&str> {
+        let Self::Row { source, .. } = self else {
+            return None;
+        };
+        match source.value_error()? {
+            ValueError::UnknownName { name, .. } => Some(name),
+            _ => None,
+        }
+    }
+}
+
+/// Every error found while reading a scene, in the order they appear
+#[derive(Debug, Error)]
+pub struct SceneErrors(pub Vec<SceneError>);
+
+impl fmt::Display for SceneErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, error) in self.0.iter().enumerate() {
+            if index != 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{error}")?;
+        }
+        // One bad definition often causes many errors later on, so sum up
+        // what is missing
+        let mut unknown_names = self
+            .0
+            .iter()
+            .filter_map(SceneError::unknown_name)
+            .collect::<Vec<_>>();
+        unknown_names.sort_unstable();
+        unknown_names.dedup();
+        if !unknown_names.is_empty() {
+            write!(f, "\nundefined names: {}", unknown_names.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+pub type SceneResult = Result<Scene, SceneErrors>;
+
+/// Reads the objects, camera, sky and tone mapping from a scene file. Rows
+/// are all checked, rather than stopping at the first mistake. Files the
+/// scene refers to are found relative to its directory.
+pub fn read_scene(path: &Path) -> SceneResult {
+    let directory = path.parent().unwrap_or(Path::new(""));
+    let path = path.display().to_string();
+    let contents = fs::read_to_string(&path).map_err(|source| {
+        SceneErrors(vec![SceneError::Io {
+            path: path.clone(),
+            source,
+        }])
+    })?;
+
+    let mut errors = Vec::new();
+    let mut names = Names::default();
+    let mut tone_mapping = None;
+    let mut lines = contents
+        .lines()
+        .enumerate()
+        .map(|(index, row)| (index + 1, row));
+
+    let camera_data = match lines.next() {
+        Some((line, row)) => {
+            let description = normalise(row);
+            parse_camera_data(&description)
+                .map_err(|error| SceneError::row(&path, line, row, error))
+        }
+        None => Err(SceneError::row(&path, 1, "", RowError::MissingCamera)),
+    }
+    .map_err(|error| errors.push(error))
+    .ok();
+
+    let sky_colours = match lines.next() {
+        Some((line, row)) => {
+            let description = normalise(row);
+            parse_sky_colour(&description)
+                .map_err(|error| SceneError::row(&path, line, row, error))
+        }
+        None => Err(SceneError::row(&path, 2, "", RowError::MissingSky)),
+    }
+    .map_err(|error| errors.push(error))
+    .ok();
+
+    let hasher = RefCell::new(SceneHasher::default());
+    hasher.borrow_mut().add(contents.as_bytes());
+    let mut objects = Vec::new();
+    for (line, row) in lines {
+        let files = RowFiles::new(directory, row, &hasher);
+        match parse_row(row, &mut names, &mut tone_mapping, files) {
+            Ok(new_objects) => {
+                objects.extend(new_objects.into_iter().flatten())
+            }
+            Err(error) => errors.push(SceneError::row(&path, line, row, error)),
+        }
+    }
+
     let (
-        look_from,
-        look_at,
-        camera_tilt,
-        fov,
-        aspect_ratio,
-        focus_distance,
-        defocus_angle,
-    ) = parse_camera_data(&first_line);
-
-    let second_line = lines
-        .next()
-        .expect("sky colour data not given")
-        .split_whitespace()
-        .collect::<String>();
-    let (sky_top_colour, sky_bottom_colour) = parse_sky_colour(&second_line);
-
-    let objects = lines
-        .filter_map(|row| {
-            parse_row(
-                row,
-                &mut points,
-                &mut colours,
-                &mut textures,
-                &mut materials,
-            )
-        })
-        .flatten()
-        .collect::<HittableList>()
-    /* .optimise()*/;
-    (
-        objects,
-        (
+        Some((
             look_from,
             look_at,
             camera_tilt,
             fov,
             aspect_ratio,
-            sky_top_colour,
-            sky_bottom_colour,
             focus_distance,
             defocus_angle,
-        ),
-    )
+        )),
+        Some((sky_top_colour, sky_bottom_colour)),
+    ) = (camera_data, sky_colours)
+    else {
+        return Err(SceneErrors(errors));
+    };
+    if !errors.is_empty() {
+        return Err(SceneErrors(errors));
+    }
+
+    let camera = CameraSettings {
+        look_from,
+        look_at,
+        tilt: camera_tilt,
+        fov,
+        aspect_ratio,
+        focus_distance,
+        defocus_angle,
+    };
+    Ok(Scene {
+        world: objects.into_iter().collect::<HittableList>().optimise(),
+        camera,
+        sky: Sky::new(sky_top_colour, sky_bottom_colour),
+        tone_mapping,
+        materials: names.materials,
+        source_hash: Some(hasher.into_inner().finish()),
+    })
 }
@@ -1,12 +1,19 @@
 use collar::CollectArray;
+use image::ImageError;
 use thiserror::Error;
 
 use crate::{
     colour::Colour,
-    scene_reader::{ReadDictionary, get_colour, get_texture, parse_f32},
+    scene_reader::{
+        ReadDictionary, RowFiles, ValueError, get_colour, get_texture,
+        parse_f32,
+    },
     textures::{
-        checker_texture::CheckerTexture, gradient_texture::GradientTexture,
-        perlin_texture::PerlinTexture, stripe_texture::StripeTexture,
+        checker_texture::CheckerTexture,
+        gradient_texture::GradientTexture,
+        image_texture::{ImageTexture, WrapMode},
+        perlin_texture::PerlinTexture,
+        stripe_texture::StripeTexture,
         texture::Texture,
     },
 };
@@ -29,6 +36,14 @@ pub enum TextureError {
         "{0}  is an invalid description for gradient texture - expected `bottom_texture, top_texture, direction`"
     )]
     Gradient(String),
+    #[error(
+        "{0} is an invalid description for image texture - expected `path, wrap_mode` where wrap_mode is repeat, mirror or clamp"
+    )]
+    Image(String),
+    #[error("unable to load image {0:?}: {1}")]
+    ImageLoad(String, ImageError),
+    #[error(transparent)]
+    Value(#[from] ValueError),
 }
 
 type TextureResult = Result<Texture, TextureError>;
@@ -37,7 +52,7 @@ pub(super) fn parse_solid(
     description: &str,
     colours: ReadDictionary<Colour>,
 ) -> TextureResult {
-    Ok(get_colour(description, colours).to_texture())
+    Ok(get_colour(description, colours)?.to_texture())
 }
 
 pub(super) fn parse_perlin(
@@ -48,8 +63,11 @@ pub(super) fn parse_perlin(
         .split_once(',')
         .ok_or(TextureError::Perlin(description.to_owned()))?;
     Ok(
-        PerlinTexture::new(parse_f32(scale), get_colour(colour_name, colours))
-            .wrap(),
+        PerlinTexture::new(
+            parse_f32(scale)?,
+            get_colour(colour_name, colours)?,
+        )
+        .wrap(),
     )
 }
 pub(super) fn parse_checkerboard(
@@ -61,9 +79,9 @@ pub(super) fn parse_checkerboard(
         .collect_array_checked()
         .map_err(|_| TextureError::Checker(description.to_owned()))?;
     Ok(CheckerTexture::new(
-        get_texture(texture_one, textures),
-        get_texture(texture_two, textures),
-        parse_f32(size),
+        get_texture(texture_one, textures)?,
+        get_texture(texture_two, textures)?,
+        parse_f32(size)?,
     )
     .wrap())
 }
@@ -77,12 +95,12 @@ pub(super) fn parse_stripe(
         .collect_array_checked()
         .map_err(|_| TextureError::Stripe(description.to_owned()))?;
     Ok(StripeTexture::new_with_dir_name(
-        get_texture(even_texture, textures),
-        get_texture(odd_texture, textures),
-        parse_f32(size),
+        get_texture(even_texture, textures)?,
+        get_texture(odd_texture, textures)?,
+        parse_f32(size)?,
         direction,
     )
-    .unwrap_or_else(|| panic!("{direction:?} is not a valid direction"))
+    .ok_or_else(|| ValueError::invalid("direction", direction))?
     .wrap())
 }
 
@@ -95,10 +113,25 @@ pub(super) fn parse_gradient(
         .collect_array_checked()
         .map_err(|_| TextureError::Gradient(description.to_owned()))?;
     Ok(GradientTexture::new_with_dir_name(
-        get_texture(bottom_texture, textures),
-        get_texture(top_texture, textures),
+        get_texture(bottom_texture, textures)?,
+        get_texture(top_texture, textures)?,
         direction,
     )
-    .unwrap_or_else(|| panic!("{direction:?} is not a valid direction"))
+    .ok_or_else(|| ValueError::invalid("direction", direction))?
     .wrap())
 }
+
+pub(super) fn parse_image(description: &str, files: RowFiles) -> TextureResult {
+    let [_, wrap_mode] = description
+        .split(',')
+        .collect_array_checked()
+        .map_err(|_| TextureError::Image(description.to_owned()))?;
+    let wrap_mode = WrapMode::from_name(wrap_mode)
+        .ok_or_else(|| ValueError::invalid("wrap mode", wrap_mode))?;
+    let path = files.path();
+    Ok(ImageTexture::load(&path, wrap_mode)
+        .map_err(|err| {
+            TextureError::ImageLoad(path.display().to_string(), err)
+        })?
+        .wrap())
+}
@@ -12,8 +12,8 @@ pub struct CheckerTexture {
 
 impl GetTexture for CheckerTexture {
     fn get_colour(&self, u: f32, v: f32) -> Colour {
-        let u_pos = (u * self.inv_scale).round() as u16;
-        let v_pos = (v * self.inv_scale).round() as u16;
+        let u_pos = (u * self.inv_scale).round() as i64;
+        let v_pos = (v * self.inv_scale).round() as i64;
 
         let sampled_texture = if u_pos.rem_euclid(2) == v_pos.rem_euclid(2) {
             self.even_texture.clone()
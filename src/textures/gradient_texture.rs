@@ -3,13 +3,24 @@ use crate::{
     textures::texture::{GetTexture, Texture},
 };
 
-#[derive(Clone, Debug)]
-enum Direction {
+#[derive(Copy, Clone, Debug)]
+pub enum Direction {
     U,
     V,
     UV,
 }
 
+impl Direction {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "u" => Some(Self::U),
+            "v" => Some(Self::V),
+            "uv" => Some(Self::UV),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct GradientTexture {
     bottom_texture: Box<Texture>,
@@ -31,22 +42,25 @@ impl GetTexture for GradientTexture {
 }
 
 impl GradientTexture {
-    pub fn new_with_dir_name(
+    pub fn new(
         bottom_texture: Texture,
         top_texture: Texture,
-        direction: &str,
-    ) -> Option<Self> {
-        let direction = match direction {
-            "u" => Direction::U,
-            "v" => Direction::V,
-            "uv" => Direction::UV,
-            _ => return None,
-        };
-        Some(Self {
+        direction: Direction,
+    ) -> Self {
+        Self {
             bottom_texture: Box::new(bottom_texture),
             top_texture: Box::new(top_texture),
             direction,
-        })
+        }
+    }
+
+    pub fn new_with_dir_name(
+        bottom_texture: Texture,
+        top_texture: Texture,
+        direction: &str,
+    ) -> Option<Self> {
+        let direction = Direction::from_name(direction)?;
+        Some(Self::new(bottom_texture, top_texture, direction))
     }
 
     pub fn wrap(self) -> Texture {
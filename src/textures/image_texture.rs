@@ -0,0 +1,113 @@
+use std::{path::Path, sync::Arc};
+
+use image::ImageResult;
+
+use crate::{
+    colour::Colour,
+    textures::texture::{GetTexture, Texture},
+};
+
+#[derive(Copy, Clone, Debug)]
+pub enum WrapMode {
+    Repeat,
+    Mirror,
+    Clamp,
+}
+
+impl WrapMode {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "repeat" => Some(Self::Repeat),
+            "mirror" => Some(Self::Mirror),
+            "clamp" => Some(Self::Clamp),
+            _ => None,
+        }
+    }
+
+    fn wrap(self, index: i64, size: u32) -> usize {
+        let size = i64::from(size);
+        let wrapped = match self {
+            Self::Repeat => index.rem_euclid(size),
+            Self::Mirror => {
+                let period = index.rem_euclid(2 * size);
+                if period < size {
+                    period
+                } else {
+                    2 * size - 1 - period
+                }
+            }
+            Self::Clamp => index.clamp(0, size - 1),
+        };
+        wrapped as usize
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ImageTexture {
+    width: u32,
+    height: u32,
+    // Shared so that cloning materials doesn't copy the image
+    pixels: Arc<[Colour]>,
+    wrap_mode: WrapMode,
+}
+
+impl GetTexture for ImageTexture {
+    fn get_colour(&self, u: f32, v: f32) -> Colour {
+        // Image rows run top to bottom, whereas v runs bottom to top
+        let x = u * self.width as f32 - 0.5;
+        let y = (1. - v) * self.height as f32 - 0.5;
+        let (x_floor, y_floor) = (x.floor(), y.floor());
+        let (x_ratio, y_ratio) = (x - x_floor, y - y_floor);
+        let (x_index, y_index) = (x_floor as i64, y_floor as i64);
+
+        let top = Colour::lerp(
+            self.texel(x_index, y_index),
+            self.texel(x_index + 1, y_index),
+            x_ratio,
+        );
+        let bottom = Colour::lerp(
+            self.texel(x_index, y_index + 1),
+            self.texel(x_index + 1, y_index + 1),
+            x_ratio,
+        );
+        Colour::lerp(top, bottom, y_ratio)
+    }
+}
+
+impl ImageTexture {
+    pub fn load(path: &Path, wrap_mode: WrapMode) -> ImageResult<Self> {
+        let image = image::open(path)?.into_rgb8();
+        let pixels = image
+            .pixels()
+            .map(|pixel| {
+                let [r, g, b] = pixel.0.map(Self::srgb_to_linear);
+                Colour::new(r, g, b)
+            })
+            .collect();
+        Ok(Self {
+            width: image.width(),
+            height: image.height(),
+            pixels,
+            wrap_mode,
+        })
+    }
+
+    fn srgb_to_linear(value: u8) -> f32 {
+        let value = f32::from(value) / 255.;
+        if value <= 0.04045 {
+            value / 12.92
+        } else {
+            ((value + 0.055) / 1.055).powf(2.4)
+        }
+    }
+
+    fn texel(&self, x: i64, y: i64) -> Colour {
+        let x = self.wrap_mode.wrap(x, self.width);
+        let y = self.wrap_mode.wrap(y, self.height);
+        self.pixels[y * self.width as usize + x]
+    }
+
+    pub fn wrap(self) -> Texture {
+        Texture::Image(self)
+    }
+}
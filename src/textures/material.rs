@@ -1,39 +1,198 @@
-use derive_more::Constructor;
-use rand::{Rng, rng};
+use std::f32::consts::PI;
 
 use crate::{
+    colour::Colour,
     geometry::{NearZero, Ray, VecRand},
     hittables::hittable::HitRecord,
-    textures::texture::Texture,
+    sampler::{Sample, Sampler},
+    textures::{
+        microfacet::Microfacet,
+        texture::{GetTexture, Texture},
+    },
 };
 
 use glam::Vec3;
 
-#[derive(Clone, Debug, Default, Constructor)]
+#[derive(Clone, Debug, Default)]
 pub struct Material {
     smoothness: f32,
     pub texture: Texture,
     pub is_glass: bool,
     refractive_index: f32,
     pub is_light: bool,
+    microfacet: Option<Microfacet>,
+    /// The order the scene defined the material in, counting from one, or
+    /// zero if it wasn't defined by name
+    pub id: u32,
+}
+
+/// A matte material of the texture
+impl From<Texture> for Material {
+    fn from(texture: Texture) -> Self {
+        Self::new_opaque(0., texture)
+    }
+}
+
+/// A matte material of the colour
+impl From<Colour> for Material {
+    fn from(colour: Colour) -> Self {
+        Self::new_opaque(0., colour.into())
+    }
+}
+
+/// The outcome of a bounce: the factor to scale the path's throughput by,
+/// and the density of the new direction when the bounce can be weighed
+/// against light sampling
+#[derive(Copy, Clone, Debug)]
+pub struct Scatter {
+    pub attenuation: Colour,
+    pub pdf: Option<f32>,
 }
 
 impl Material {
+    pub(crate) const fn new(
+        smoothness: f32,
+        texture: Texture,
+        is_glass: bool,
+        refractive_index: f32,
+        is_light: bool,
+        microfacet: Option<Microfacet>,
+        id: u32,
+    ) -> Self {
+        Self {
+            smoothness,
+            texture,
+            is_glass,
+            refractive_index,
+            is_light,
+            microfacet,
+            id,
+        }
+    }
+
     pub const fn new_opaque(smoothness: f32, texture: Texture) -> Self {
-        Self::new(smoothness, texture, false, 0., false)
+        Self::new(smoothness, texture, false, 0., false, None, 0)
     }
 
     pub const fn new_glass(refractive_index: f32, texture: Texture) -> Self {
         let refractive_index = f32::max(refractive_index, 0.000_000_1);
-        Self::new(0., texture, true, refractive_index, false)
+        Self::new(0., texture, true, refractive_index, false, None, 0)
     }
 
     pub const fn new_light(texture: Texture) -> Self {
-        Self::new(0., texture, false, 0., true)
+        Self::new(0., texture, false, 0., true, None, 0)
+    }
+
+    pub fn new_pbr(roughness: f32, metallic: f32, texture: Texture) -> Self {
+        let microfacet = Microfacet::new(roughness, metallic);
+        Self::new(0., texture, false, 0., false, Some(microfacet), 0)
+    }
+
+    pub fn with_texture(mut self, texture: impl Into<Texture>) -> Self {
+        self.texture = texture.into();
+        self
+    }
+
+    /// How mirror-like an opaque material is, from zero for matte to one
+    pub fn with_smoothness(mut self, smoothness: f32) -> Self {
+        self.smoothness = smoothness;
+        self
+    }
+
+    pub fn with_id(mut self, id: u32) -> Self {
+        self.id = id;
+        self
+    }
+
+    fn is_diffuse(&self) -> bool {
+        !self.is_glass
+            && !self.is_light
+            && self.microfacet.is_none()
+            && self.smoothness == 0.
+    }
+
+    /// Whether the reflectance can be evaluated for any pair of directions,
+    /// so direct lighting can be sampled explicitly
+    pub fn samples_lights(&self) -> bool {
+        self.is_diffuse() || self.microfacet.is_some()
+    }
+
+    /// Bounces `ray` off the surface, returning `None` if it was absorbed
+    pub fn scatter(
+        &self,
+        ray: &mut Ray,
+        record: &HitRecord,
+        sampler: &mut Sampler,
+    ) -> Option<Scatter> {
+        let colour = self.texture.get_colour(record.u, record.v);
+        if let Some(microfacet) = self.microfacet {
+            let outgoing = -ray.direction.normalize();
+            let normal = Self::facing_normal(record, outgoing);
+            let incoming = microfacet.sample(normal, outgoing, sampler)?;
+            let (reflectance, pdf) =
+                microfacet.evaluate(normal, outgoing, incoming, colour);
+            if pdf <= 0. {
+                return None;
+            }
+            ray.origin = record.collision_point;
+            ray.direction = incoming;
+            return Some(Scatter {
+                attenuation: reflectance * (1. / pdf),
+                pdf: Some(pdf),
+            });
+        }
+
+        if self.is_glass {
+            self.refract(ray, record, sampler)
+        } else {
+            self.lerp_reflect(ray, record, sampler)
+        };
+        let pdf = self.is_diffuse().then(|| {
+            record.normal_vector.dot(ray.direction.normalize()).max(0.) / PI
+        });
+        Some(Scatter {
+            attenuation: colour,
+            pdf,
+        })
+    }
+
+    /// Reflectance scaled by the cosine term for light arriving along the
+    /// unit vector `incoming` and leaving back along `ray_direction`, with
+    /// the density `scatter` would have picked it with
+    pub fn evaluate(
+        &self,
+        record: &HitRecord,
+        ray_direction: Vec3,
+        incoming: Vec3,
+    ) -> Option<(Colour, f32)> {
+        let colour = self.texture.get_colour(record.u, record.v);
+        if let Some(microfacet) = self.microfacet {
+            let outgoing = -ray_direction.normalize();
+            let normal = Self::facing_normal(record, outgoing);
+            return Some(
+                microfacet.evaluate(normal, outgoing, incoming, colour),
+            );
+        }
+        self.is_diffuse().then(|| {
+            let cosine = record.normal_vector.dot(incoming).max(0.);
+            (colour * (cosine / PI), cosine / PI)
+        })
+    }
+
+    fn facing_normal(record: &HitRecord, outgoing: Vec3) -> Vec3 {
+        if record.normal_vector.dot(outgoing) < 0. {
+            -record.normal_vector
+        } else {
+            record.normal_vector
+        }
     }
 
-    pub fn diffuse_reflection(record: &HitRecord) -> Vec3 {
-        let scatter_direction = record.normal_vector + Vec3::rand_unit_vector();
+    pub fn diffuse_reflection(
+        record: &HitRecord,
+        sampler: &mut Sampler,
+    ) -> Vec3 {
+        let scatter_direction =
+            record.normal_vector + Vec3::rand_unit_vector(sampler);
 
         if scatter_direction.near_zero() {
             record.normal_vector
@@ -46,8 +205,13 @@ impl Material {
         ray.direction.reflect(record.normal_vector)
     }
 
-    pub fn lerp_reflect(&self, ray: &mut Ray, record: &HitRecord) {
-        let diffuse_direction = Self::diffuse_reflection(record);
+    pub fn lerp_reflect(
+        &self,
+        ray: &mut Ray,
+        record: &HitRecord,
+        sampler: &mut Sampler,
+    ) {
+        let diffuse_direction = Self::diffuse_reflection(record, sampler);
         let specular_direction = Self::specular_reflection(ray, record);
         let direction = self.smoothness * specular_direction
             + (1. - self.smoothness) * diffuse_direction;
@@ -55,7 +219,12 @@ impl Material {
         ray.direction = direction
     }
 
-    pub fn refract(&self, ray: &mut Ray, record: &HitRecord) {
+    pub fn refract(
+        &self,
+        ray: &mut Ray,
+        record: &HitRecord,
+        sampler: &mut Sampler,
+    ) {
         let refractive_index = if record.front_face {
             1. / self.refractive_index
         } else {
@@ -66,11 +235,12 @@ impl Material {
         let cos_theta = f32::min(1., -unit.dot(record.normal_vector));
         let sin_theta = (1f32 - cos_theta * cos_theta).sqrt();
 
-        let mut rng = rng();
+        // Drawn up front so every path asks the sampler for the same
+        // dimensions
+        let choice = sampler.get_1d();
         let direction = if refractive_index * sin_theta < 1.
-            || rng.random_bool(
-                Self::reflectance(cos_theta, refractive_index).into(),
-            ) {
+            || choice < Self::reflectance(cos_theta, refractive_index)
+        {
             unit.refract(record.normal_vector, refractive_index)
         } else {
             unit.reflect(record.normal_vector)
@@ -0,0 +1,121 @@
+use std::f32::consts::{PI, TAU};
+
+use crate::{
+    colour::Colour,
+    geometry::VecRand,
+    sampler::{Sample, Sampler},
+};
+
+use glam::Vec3;
+
+/// Cook-Torrance reflection with a GGX distribution over a Lambertian base,
+/// following the metallic/roughness convention
+#[derive(Copy, Clone, Debug)]
+pub struct Microfacet {
+    alpha: f32,
+    metallic: f32,
+}
+
+impl Microfacet {
+    // Reflectance at normal incidence of a typical dielectric
+    const DIELECTRIC_REFLECTANCE: Colour = Colour::new(0.04, 0.04, 0.04);
+    // Perfectly smooth surfaces make the distribution a delta function
+    const MIN_ALPHA: f32 = 1e-3;
+
+    pub fn new(roughness: f32, metallic: f32) -> Self {
+        Self {
+            alpha: roughness.clamp(0., 1.).powi(2).max(Self::MIN_ALPHA),
+            metallic: metallic.clamp(0., 1.),
+        }
+    }
+
+    fn specular_probability(&self) -> f32 {
+        0.5 + 0.5 * self.metallic
+    }
+
+    fn distribution(&self, cos_half: f32) -> f32 {
+        let alpha_squared = self.alpha * self.alpha;
+        let denominator = cos_half * cos_half * (alpha_squared - 1.) + 1.;
+        alpha_squared / (PI * denominator * denominator)
+    }
+
+    fn smith_masking(&self, cosine: f32) -> f32 {
+        let alpha_squared = self.alpha * self.alpha;
+        2. * cosine
+            / (cosine
+                + (alpha_squared + (1. - alpha_squared) * cosine * cosine)
+                    .sqrt())
+    }
+
+    /// Picks a direction towards the light, given the unit `normal` and unit
+    /// `outgoing` direction towards the viewer
+    pub fn sample(
+        &self,
+        normal: Vec3,
+        outgoing: Vec3,
+        sampler: &mut Sampler,
+    ) -> Option<Vec3> {
+        let incoming = if sampler.get_1d() < self.specular_probability() {
+            let sample = sampler.get_2d();
+            let (first, second) = (sample.x, sample.y);
+            let alpha_squared = self.alpha * self.alpha;
+            let cos_theta =
+                ((1. - first) / (1. + first * (alpha_squared - 1.))).sqrt();
+            let sin_theta = (1. - cos_theta * cos_theta).sqrt();
+            let phi = TAU * second;
+            let (tangent, bitangent) = normal.any_orthonormal_pair();
+            let half = (phi.cos() * sin_theta) * tangent
+                + (phi.sin() * sin_theta) * bitangent
+                + cos_theta * normal;
+            2. * outgoing.dot(half) * half - outgoing
+        } else {
+            (normal + Vec3::rand_unit_vector(sampler))
+                .try_normalize()
+                .unwrap_or(normal)
+        };
+        (normal.dot(incoming) > 0.).then_some(incoming)
+    }
+
+    /// The reflectance scaled by the cosine term, and the density with which
+    /// `sample` picks `incoming`
+    pub fn evaluate(
+        &self,
+        normal: Vec3,
+        outgoing: Vec3,
+        incoming: Vec3,
+        base_colour: Colour,
+    ) -> (Colour, f32) {
+        let cos_outgoing = normal.dot(outgoing);
+        let cos_incoming = normal.dot(incoming);
+        if cos_outgoing <= 0. || cos_incoming <= 0. {
+            return (Colour::BLACK, 0.);
+        }
+        let half = (outgoing + incoming).normalize();
+        let cos_half = normal.dot(half);
+        let outgoing_dot_half = outgoing.dot(half).max(f32::EPSILON);
+
+        let distribution = self.distribution(cos_half);
+        let masking =
+            self.smith_masking(cos_outgoing) * self.smith_masking(cos_incoming);
+        let base_reflectance = Colour::lerp(
+            Self::DIELECTRIC_REFLECTANCE,
+            base_colour,
+            self.metallic,
+        );
+        let fresnel = base_reflectance
+            + (Colour::WHITE - base_reflectance)
+                * (1. - outgoing_dot_half).powi(5);
+
+        let specular = fresnel
+            * (distribution * masking / (4. * cos_outgoing * cos_incoming));
+        let diffuse = (Colour::WHITE - fresnel)
+            * base_colour
+            * ((1. - self.metallic) / PI);
+
+        let specular_probability = self.specular_probability();
+        let pdf = specular_probability * distribution * cos_half
+            / (4. * outgoing_dot_half)
+            + (1. - specular_probability) * cos_incoming / PI;
+        ((specular + diffuse) * cos_incoming, pdf)
+    }
+}
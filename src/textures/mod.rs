@@ -1,7 +1,9 @@
-pub mod checker_texture;
-pub mod gradient_texture;
-pub mod material;
-pub mod perlin_texture;
-pub mod solid_texture;
-pub mod stripe_texture;
-pub mod texture;
+pub(crate) mod checker_texture;
+pub(crate) mod gradient_texture;
+pub(crate) mod image_texture;
+pub(crate) mod material;
+pub(crate) mod microfacet;
+pub(crate) mod perlin_texture;
+pub(crate) mod solid_texture;
+pub(crate) mod stripe_texture;
+pub(crate) mod texture;
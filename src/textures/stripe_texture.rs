@@ -3,12 +3,22 @@ use crate::{
     textures::texture::{GetTexture, Texture},
 };
 
-#[derive(Clone, Debug)]
-enum Direction {
+#[derive(Copy, Clone, Debug)]
+pub enum Direction {
     U,
     V,
 }
 
+impl Direction {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "u" => Some(Self::U),
+            "v" => Some(Self::V),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct StripeTexture {
     even_texture: Box<Texture>,
@@ -19,8 +29,8 @@ pub struct StripeTexture {
 
 impl GetTexture for StripeTexture {
     fn get_colour(&self, u: f32, v: f32) -> Colour {
-        let u_pos = (u * self.inv_scale).round() as u16;
-        let v_pos = (v * self.inv_scale).round() as u16;
+        let u_pos = (u * self.inv_scale).round() as i64;
+        let v_pos = (v * self.inv_scale).round() as i64;
         let to_compare = match self.direction {
             Direction::U => u_pos,
             Direction::V => v_pos,
@@ -37,23 +47,28 @@ impl GetTexture for StripeTexture {
 }
 
 impl StripeTexture {
-    pub fn new_with_dir_name(
+    pub fn new(
         even_texture: Texture,
         odd_texture: Texture,
         size: f32,
-        direction: &str,
-    ) -> Option<Self> {
-        let direction = match direction {
-            "u" => Direction::U,
-            "v" => Direction::V,
-            _ => return None,
-        };
-        Some(Self {
+        direction: Direction,
+    ) -> Self {
+        Self {
             even_texture: Box::new(even_texture),
             odd_texture: Box::new(odd_texture),
             inv_scale: 1. / size,
             direction,
-        })
+        }
+    }
+
+    pub fn new_with_dir_name(
+        even_texture: Texture,
+        odd_texture: Texture,
+        size: f32,
+        direction: &str,
+    ) -> Option<Self> {
+        let direction = Direction::from_name(direction)?;
+        Some(Self::new(even_texture, odd_texture, size, direction))
     }
 
     pub fn wrap(self) -> Texture {
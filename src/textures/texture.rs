@@ -1,9 +1,16 @@
+use std::path::Path;
+
+use image::ImageResult;
+
 use crate::{
     colour::Colour,
     textures::{
-        checker_texture::CheckerTexture, gradient_texture::GradientTexture,
-        perlin_texture::PerlinTexture, solid_texture::SolidTexture,
-        stripe_texture::StripeTexture,
+        checker_texture::CheckerTexture,
+        gradient_texture::{self, GradientTexture},
+        image_texture::{ImageTexture, WrapMode},
+        perlin_texture::PerlinTexture,
+        solid_texture::SolidTexture,
+        stripe_texture::{self, StripeTexture},
     },
 };
 
@@ -18,6 +25,7 @@ pub enum Texture {
     Perlin(PerlinTexture),
     Stripe(StripeTexture),
     Gradient(GradientTexture),
+    Image(ImageTexture),
     UV,
 }
 
@@ -27,6 +35,57 @@ impl Default for Texture {
     }
 }
 
+impl Texture {
+    pub fn solid(colour: Colour) -> Self {
+        SolidTexture::new(colour).wrap()
+    }
+
+    /// Squares of `size` in u and v, alternating between `even` and `odd`
+    pub fn checker(
+        even: impl Into<Texture>,
+        odd: impl Into<Texture>,
+        size: f32,
+    ) -> Self {
+        CheckerTexture::new(even.into(), odd.into(), size).wrap()
+    }
+
+    /// Bands of `size` across `direction`, alternating between `even` and
+    /// `odd`
+    pub fn stripe(
+        even: impl Into<Texture>,
+        odd: impl Into<Texture>,
+        size: f32,
+        direction: stripe_texture::Direction,
+    ) -> Self {
+        StripeTexture::new(even.into(), odd.into(), size, direction).wrap()
+    }
+
+    /// A blend from `bottom` to `top` along `direction`
+    pub fn gradient(
+        bottom: impl Into<Texture>,
+        top: impl Into<Texture>,
+        direction: gradient_texture::Direction,
+    ) -> Self {
+        GradientTexture::new(bottom.into(), top.into(), direction).wrap()
+    }
+
+    /// Noise in shades of `colour`, with features `scale` apart
+    pub fn perlin(scale: f32, colour: Colour) -> Self {
+        PerlinTexture::new(scale, colour).wrap()
+    }
+
+    /// The image at `path`, read from beyond its edges by `wrap_mode`
+    pub fn image(path: &Path, wrap_mode: WrapMode) -> ImageResult<Self> {
+        Ok(ImageTexture::load(path, wrap_mode)?.wrap())
+    }
+}
+
+impl From<Colour> for Texture {
+    fn from(colour: Colour) -> Self {
+        colour.to_texture()
+    }
+}
+
 impl GetTexture for Texture {
     fn get_colour(&self, u: f32, v: f32) -> Colour {
         match self {
@@ -39,6 +98,7 @@ impl GetTexture for Texture {
             Texture::Gradient(gradient_texture) => {
                 gradient_texture.get_colour(u, v)
             }
+            Texture::Image(image_texture) => image_texture.get_colour(u, v),
             Texture::UV => Colour::new(u, v, 0.),
         }
     }
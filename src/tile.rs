@@ -0,0 +1,110 @@
+use std::f32::consts::PI;
+
+use clap::ValueEnum;
+use serde::Deserialize;
+
+/// The order tiles are handed out to be rendered in
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum TileOrder {
+    /// Left to right, then top to bottom
+    Scanline,
+    /// Outwards from the centre of the image, where the subject usually is
+    Spiral,
+    /// Along a Hilbert curve, which keeps tiles rendered around the same
+    /// time close together
+    #[default]
+    Hilbert,
+}
+
+/// A rectangle of pixels rendered together
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Tile {
+    pub left: u32,
+    pub top: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Tile {
+    /// The tile's pixels, row by row
+    pub fn pixels(self) -> impl Iterator<Item = (u32, u32)> {
+        (self.top..self.top + self.height).flat_map(move |j| {
+            (self.left..self.left + self.width).map(move |i| (i, j))
+        })
+    }
+}
+
+/// Splits an image into tiles of `size` pixels square, or smaller along
+/// the right and bottom edges, in `order`
+pub fn tiles(
+    width: u32,
+    height: u32,
+    size: u32,
+    order: TileOrder,
+) -> Vec<Tile> {
+    let size = size.max(1);
+    let (columns, rows) = (width.div_ceil(size), height.div_ceil(size));
+    let mut positions = (0..rows)
+        .flat_map(|row| (0..columns).map(move |column| (column, row)))
+        .collect::<Vec<_>>();
+    match order {
+        TileOrder::Scanline => {}
+        TileOrder::Spiral => {
+            let centre = (
+                columns.saturating_sub(1) as f32 / 2.,
+                rows.saturating_sub(1) as f32 / 2.,
+            );
+            // Each ring of tiles around the centre in turn, going round
+            // each ring
+            positions.sort_by(|&a, &b| {
+                spiral_key(a, centre).total_cmp(&spiral_key(b, centre))
+            });
+        }
+        TileOrder::Hilbert => {
+            let side = columns.max(rows).next_power_of_two();
+            positions.sort_by_key(|&position| hilbert_index(side, position));
+        }
+    }
+    positions
+        .into_iter()
+        .map(|(column, row)| Tile {
+            left: column * size,
+            top: row * size,
+            width: size.min(width - column * size),
+            height: size.min(height - row * size),
+        })
+        .collect()
+}
+
+/// The tile's ring around the centre, with its angle around the ring as
+/// the fractional part
+fn spiral_key((column, row): (u32, u32), centre: (f32, f32)) -> f32 {
+    let (x, y) = (column as f32 - centre.0, row as f32 - centre.1);
+    let ring = x.abs().max(y.abs()).round();
+    let angle = (y.atan2(x) + PI) / (2. * PI);
+    ring + angle.min(0.999)
+}
+
+/// How far along a Hilbert curve filling a `side` by `side` grid the cell
+/// at `position` is
+fn hilbert_index(side: u32, (mut x, mut y): (u32, u32)) -> u64 {
+    let mut index = 0;
+    let mut scale = side / 2;
+    while scale > 0 {
+        let rx = u32::from(x & scale != 0);
+        let ry = u32::from(y & scale != 0);
+        index += u64::from(scale) * u64::from(scale) * u64::from((3 * rx) ^ ry);
+        // Turn the quadrant so that the curve within it starts and ends
+        // where the curve around it expects
+        if ry == 0 {
+            if rx == 1 {
+                x = scale - 1 - (x & (scale - 1));
+                y = scale - 1 - (y & (scale - 1));
+            }
+            (x, y) = (y, x);
+        }
+        scale /= 2;
+    }
+    index
+}
@@ -0,0 +1,108 @@
+use clap::ValueEnum;
+use glam::{Mat3, Vec3};
+use serde::Deserialize;
+
+use crate::colour::Colour;
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum ToneMapper {
+    /// Clip values outside [0, 1]
+    #[default]
+    Clamp,
+    /// x / (1 + x)
+    Reinhard,
+    /// Reinhard, reaching white at the white point instead of infinity
+    ExtendedReinhard,
+    /// Narkowicz's fit of the ACES filmic curve
+    Aces,
+    /// Wrensch's polynomial fit of Sobotka's AgX
+    Agx,
+}
+
+impl ToneMapper {
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::from_str(name, true).ok()
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct ToneMapping {
+    pub operator: ToneMapper,
+    /// Stops to scale the linear radiance by before mapping
+    pub exposure: f32,
+    /// Radiance mapped to white by the extended Reinhard operator
+    pub white_point: f32,
+}
+
+impl Default for ToneMapping {
+    fn default() -> Self {
+        Self {
+            operator: ToneMapper::default(),
+            exposure: 0.,
+            white_point: 4.,
+        }
+    }
+}
+
+impl ToneMapping {
+    /// Maps linear radiance into linear display values in [0, 1]
+    pub fn apply(&self, colour: Colour) -> Colour {
+        let colour = colour * self.exposure.exp2();
+        let mapped = match self.operator {
+            ToneMapper::Clamp => colour,
+            ToneMapper::Reinhard => colour.map(|x| x / (1. + x)),
+            ToneMapper::ExtendedReinhard => {
+                let white_squared = self.white_point * self.white_point;
+                colour.map(|x| x * (1. + x / white_squared) / (1. + x))
+            }
+            ToneMapper::Aces => colour.map(|x| {
+                (x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14)
+            }),
+            ToneMapper::Agx => Self::agx(colour),
+        };
+        mapped.map(|x| x.clamp(0., 1.))
+    }
+
+    // https://iolite-engine.com/blog_posts/minimal_agx_implementation
+    fn agx(colour: Colour) -> Colour {
+        const MIN_EV: f32 = -12.473_931;
+        const MAX_EV: f32 = 4.026_069;
+        let inset = Mat3::from_cols_array(&[
+            0.842_479_06,
+            0.042_328_24,
+            0.042_375_65,
+            0.078_433_6,
+            0.878_468_6,
+            0.078_433_6,
+            0.079_223_75,
+            0.079_166_13,
+            0.879_143,
+        ]);
+        let outset = Mat3::from_cols_array(&[
+            1.196_879,
+            -0.052_896_85,
+            -0.052_971_635,
+            -0.098_020_88,
+            1.151_903_1,
+            -0.098_043_45,
+            -0.099_029_74,
+            -0.098_961_18,
+            1.151_073_7,
+        ]);
+
+        let encoded = inset * Vec3::from(colour.map(|x| x.max(1e-10)));
+        let normalised = encoded.to_array().map(|x| {
+            (x.log2().clamp(MIN_EV, MAX_EV) - MIN_EV) / (MAX_EV - MIN_EV)
+        });
+        let contrast = Vec3::from_array(normalised.map(|x| {
+            let (x2, x4) = (x * x, x * x * x * x);
+            15.5 * x4 * x2 - 40.14 * x4 * x + 31.96 * x4 - 6.868 * x2 * x
+                + 0.4298 * x2
+                + 0.1191 * x
+                - 0.002_32
+        }));
+        // The curve produces display values, so undo their ~2.2 gamma
+        Colour::from(outset * contrast).map(|x| x.max(0.).powf(2.2))
+    }
+}